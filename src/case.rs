@@ -0,0 +1,72 @@
+use std::borrow::Cow;
+
+/// A naming convention to rename struct fields and enum variants into.
+///
+/// Names are split into words at `_`, `-` and changes of case, so `user_id`, `userId`,
+/// `UserId` and `user-id` all become the same words.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCase {
+    /// Leave names as they are
+    #[default]
+    Unchanged,
+    /// `userId`
+    CamelCase,
+    /// `UserId`
+    PascalCase,
+    /// `user_id`
+    SnakeCase,
+    /// `USER_ID`
+    ScreamingSnakeCase,
+    /// `user-id`
+    KebabCase,
+}
+
+impl KeyCase {
+    /// Rename `name` into this case
+    pub fn apply<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let words = || words(name).into_iter().map(str::to_lowercase);
+        Cow::Owned(match self {
+            Self::Unchanged => return Cow::Borrowed(name),
+            Self::CamelCase => words()
+                .enumerate()
+                .map(|(i, word)| if i == 0 { word } else { capitalize(&word) })
+                .collect(),
+            Self::PascalCase => words().map(|word| capitalize(&word)).collect(),
+            Self::SnakeCase => words().collect::<Vec<_>>().join("_"),
+            Self::ScreamingSnakeCase => words().collect::<Vec<_>>().join("_").to_uppercase(),
+            Self::KebabCase => words().collect::<Vec<_>>().join("-"),
+        })
+    }
+}
+
+/// Split a name into words, treating runs of capitals like `ID` in `userID` as one word
+fn words(name: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in name.split(['_', '-']) {
+        let chars = part.char_indices().collect::<Vec<_>>();
+        let mut start = 0;
+        for (i, &(index, c)) in chars.iter().enumerate().skip(1) {
+            let previous = chars[i - 1].1;
+            let next = chars.get(i + 1).map(|&(_, c)| c);
+            // A capital starts a word after a lowercase letter or digit, or when it's the last
+            // capital of a run followed by a lowercase letter, as `S` in `HTTPServer`
+            let boundary = c.is_uppercase()
+                && (!previous.is_uppercase() || next.is_some_and(char::is_lowercase));
+            if boundary {
+                words.push(&part[start..index]);
+                start = index;
+            }
+        }
+        words.push(&part[start..]);
+    }
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -1,226 +1,364 @@
-use std::{
-    io::{BufRead, BufReader, Read},
-    num::{ParseFloatError, ParseIntError},
-    str::FromStr,
-};
+use std::{io::Read, num::ParseFloatError, str::FromStr};
 
 use serde::{
-    de::{DeserializeOwned, IntoDeserializer, MapAccess, SeqAccess, Visitor},
+    de::{
+        DeserializeOwned, Expected, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess, Unexpected,
+        Visitor,
+    },
     forward_to_deserialize_any,
 };
 
-use crate::{Error, Result};
+pub use crate::tokenizer::Syntax;
+#[cfg(feature = "async")]
+use crate::{ASYNC_CHANNEL_CAPACITY, ASYNC_CHUNK_SIZE};
+use crate::{
+    Error, KeyCase, Result,
+    error::Segment,
+    tokenizer::{Position, Token, Tokenizer, is_number_literal},
+};
+
+/// How closely JSON values must match the Rust types they're deserialized into
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Coercion {
+    /// Each type only accepts its own kind of JSON value
+    #[default]
+    Exact,
+    /// Also accept alternate encodings that convert without loss: numbers and bools written as
+    /// strings, `0` and `1` for bools, and integers written with a zero fraction such as `5.0`
+    Lenient,
+}
+
+/// How object keys and enum variant names are matched against the names a type expects
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NameMatching {
+    /// Names must match exactly
+    #[default]
+    Exact,
+    /// Names match ignoring case, so `UserID` matches `userId`
+    CaseInsensitive,
+    /// Names match ignoring case, `_` and `-`, so `user_id`, `user-id` and `UserID` all match
+    /// `userId`
+    Normalized,
+}
+
+impl NameMatching {
+    fn normalize(self, name: &str) -> String {
+        match self {
+            Self::Exact => name.to_string(),
+            Self::CaseInsensitive => name.to_lowercase(),
+            Self::Normalized => name
+                .chars()
+                .filter(|c| *c != '_' && *c != '-')
+                .flat_map(char::to_lowercase)
+                .collect(),
+        }
+    }
+
+    /// Find the expected name `name` stands for once the expected names are renamed into `case`,
+    /// or `None` to use `name` as it is
+    fn resolve(
+        self,
+        name: &str,
+        expected: &'static [&'static str],
+        case: KeyCase,
+    ) -> Result<Option<&'static str>> {
+        if expected.contains(&name) || (self == Self::Exact && case == KeyCase::Unchanged) {
+            return Ok(None);
+        }
+        let normalized = self.normalize(name);
+        let candidates = expected
+            .iter()
+            .copied()
+            .filter(|candidate| self.normalize(&case.apply(candidate)) == normalized)
+            .collect::<Vec<_>>();
+        match candidates[..] {
+            [] => Ok(None),
+            [candidate] => Ok(Some(candidate)),
+            _ => Err(Error::AmbiguousName {
+                name: name.to_string(),
+                candidates,
+            }),
+        }
+    }
+}
 
 pub struct Deserializer<R: Read> {
-    input: BufReader<R>,
-    hold: Option<u8>,
+    tokens: Tokenizer<R>,
+    /// Where the last token taken starts
+    position: Position,
+    /// Whether numbers that don't fit their type are errors rather than rounded to a float
+    strict_numbers: bool,
+    /// Whether integers may also be written as strings
+    string_integers: bool,
+    coercion: Coercion,
+    names: NameMatching,
+    keys: KeyCase,
 }
 
 impl<R: Read> Deserializer<R> {
     pub fn new(input: R) -> Self {
+        let tokens = Tokenizer::new(input);
         Self {
-            input: BufReader::new(input),
-            hold: None,
+            position: tokens.position(),
+            tokens,
+            strict_numbers: false,
+            string_integers: false,
+            coercion: Coercion::Exact,
+            names: NameMatching::Exact,
+            keys: KeyCase::Unchanged,
         }
     }
 
-    fn next(&mut self) -> Result<u8> {
-        if let Some(hold) = self.hold.take() {
-            return Ok(hold);
-        }
-        let mut buf = [0];
-        loop {
-            self.input.read_exact(&mut buf)?;
-            if !(buf[0] as char).is_whitespace() {
-                break;
-            }
-        }
-        Ok(buf[0])
+    /// Set the flavour of JSON to accept
+    pub fn syntax(mut self, syntax: Syntax) -> Self {
+        self.tokens = self.tokens.syntax(syntax);
+        self
     }
 
-    fn expect_next(&mut self, c: char) -> Result<()> {
-        let next = self.next()? as char;
-        if next == c {
-            Ok(())
-        } else {
-            Err(Error::Unexpected {
-                expected: Some(c.to_string()),
-                found: next.to_string(),
-            })
-        }
+    /// Reject numbers too large for any number type instead of rounding them to an f64.
+    ///
+    /// Integers beyond 128 bits and floats beyond the range of an f64 then return
+    /// [`Error::NumberOutOfRange`].
+    pub fn strict_numbers(mut self, strict: bool) -> Self {
+        self.strict_numbers = strict;
+        self
     }
 
-    fn peek(&mut self) -> Result<u8> {
-        if let Some(hold) = self.hold {
-            return Ok(hold);
-        }
-        let mut buf = [0];
-        loop {
-            self.input.read_exact(&mut buf)?;
-            if !(buf[0] as char).is_whitespace() {
-                break;
-            }
-        }
-        self.hold = Some(buf[0]);
-        Ok(buf[0])
+    /// Also accept integers written as strings, such as `"9007199254740993"`.
+    ///
+    /// This reads what a serializer using [`IntegerFormat`](crate::IntegerFormat) writes.
+    pub fn string_integers(mut self, accept: bool) -> Self {
+        self.string_integers = accept;
+        self
     }
 
-    /// Collect the digits of an integer
-    fn get_integer(&mut self, mut buf: Vec<u8>) -> Result<String> {
-        loop {
-            match self.peek() {
-                Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                    break;
-                }
-                Err(e) => return Err(e),
-                Ok(peek) => {
-                    if !peek.is_ascii_digit() {
-                        break;
-                    }
-                }
-            }
-            buf.push(self.next()?);
-        }
-        Ok(String::from_utf8(buf)?)
+    /// Set which alternate encodings of numbers and bools to accept
+    pub fn coercion(mut self, coercion: Coercion) -> Self {
+        self.coercion = coercion;
+        self
     }
 
-    /// Parse a signed integer
-    fn parse_int<V: FromStr<Err = ParseIntError>>(&mut self) -> Result<V> {
-        let mut buf = Vec::new();
-        if self.peek()? == b'-' {
-            buf.push(self.next()?);
-        }
-        let string = self.get_integer(buf)?;
-        // Previous checks should guarentee that the string is parseable
-        Ok(string.parse()?)
+    /// Set how struct fields and enum variants are matched by name
+    pub fn name_matching(mut self, names: NameMatching) -> Self {
+        self.names = names;
+        self
     }
 
-    /// Parse an unsigned integer
-    fn parse_uint<V: FromStr<Err = ParseIntError>>(&mut self) -> Result<V> {
-        let string = self.get_integer(Vec::new())?;
-        Ok(string.parse()?)
+    /// Expect struct fields and enum variants renamed into `case`, as written by a serializer
+    /// with the same [`key_case`](crate::Serializer::key_case)
+    ///
+    /// Internally tagged, adjacently tagged and untagged enums and `#[serde(flatten)]` fields
+    /// are buffered by serde before it knows which names to expect, so their keys must match
+    /// exactly. Use `#[serde(rename_all)]` on those types instead.
+    pub fn key_case(mut self, case: KeyCase) -> Self {
+        self.keys = case;
+        self
     }
 
-    /// Parse a floating-point number
-    fn parse_float<V: FromStr<Err = ParseFloatError>>(&mut self) -> Result<V> {
-        let mut buf = Vec::new();
-        if self.peek()? == b'-' {
-            buf.push(self.next()?);
-        }
-        let mut string = self.get_integer(buf)?;
-        if self.peek()? == b'.' {
-            let buf = vec![self.next()?];
-            string += &self.get_integer(buf)?;
+    /// Find the expected field or variant name `name` stands for
+    fn resolve(
+        &self,
+        name: &str,
+        expected: &'static [&'static str],
+    ) -> Result<Option<&'static str>> {
+        self.names.resolve(name, expected, self.keys)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        match self.tokens.next_event()? {
+            Some(event) => {
+                self.position = event.position;
+                Ok(event.token)
+            }
+            None => Err(Error::Eof),
         }
-        let peek = self.peek()? as char;
-        if peek == 'e' || peek == 'E' {
-            let buf = vec![self.next()?];
-            string += &self.get_integer(buf)?;
+    }
+
+    fn peek(&mut self) -> Result<&Token> {
+        match self.tokens.peek_event()? {
+            Some(event) => Ok(&event.token),
+            None => Err(Error::Eof),
         }
+    }
 
-        Ok(string.parse()?)
+    /// Point an error at the last token taken, unless it already has a position
+    fn locate(&self, e: Error) -> Error {
+        e.at_position(self.position)
     }
 
-    // Unsure how to best test this since it only is used in deserialize_any
-    /// Collect the digits of a number and visits either an i64, u64, or f64
-    fn parse_number<'de, V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
-        let mut buf = Vec::new();
-        let mut signed = false;
-        let mut float = false;
-        if self.peek()? == b'-' {
-            signed = true;
-            buf.push(self.next()?);
-        }
-        let mut string = self.get_integer(buf)?;
-        if self.peek()? == b'.' {
-            float = true;
-            let buf = vec![self.next()?];
-            string += &self.get_integer(buf)?;
+    fn expect_next(&mut self, token: Token, expected: &dyn Expected) -> Result<()> {
+        let next = self.next()?;
+        if next == token {
+            Ok(())
+        } else {
+            Err(invalid_type(&next, expected))
         }
-        let peek = self.peek()? as char;
-        if peek == 'e' || peek == 'E' {
-            float = true;
-            let buf = vec![self.next()?];
-            string += &self.get_integer(buf)?;
+    }
+
+    /// Take the text of a number literal, which may be written as a string if `strings` is set,
+    /// and whether it was
+    fn next_number(&mut self, expected: &dyn Expected, strings: bool) -> Result<(String, bool)> {
+        match self.next()? {
+            Token::Number(n) => Ok((n, false)),
+            Token::String(s) if strings && is_number_literal(&s) => Ok((s, true)),
+            Token::String(s) if strings => Err(serde::de::Error::invalid_value(
+                Unexpected::Str(&s),
+                expected,
+            )),
+            t => Err(invalid_type(&t, expected)),
         }
+    }
 
-        if float {
-            visitor.visit_f64(string.parse()?)
-        } else if signed {
-            visitor.visit_i64(string.parse()?)
-        } else {
-            visitor.visit_u64(string.parse()?)
+    /// Parse an integer, which may be a string if that's enabled
+    fn parse_int<V: FromStr>(&mut self, expected: &dyn Expected) -> Result<V> {
+        let lenient = self.coercion == Coercion::Lenient;
+        let number = match self.next()? {
+            Token::Number(n) => n,
+            Token::String(s) if lenient || self.string_integers => {
+                let integer = !s.contains(['.', 'e', 'E']);
+                if !is_number_literal(&s) || !(lenient || integer) {
+                    return Err(serde::de::Error::invalid_value(
+                        Unexpected::Str(&s),
+                        expected,
+                    ));
+                }
+                s
+            }
+            t => return Err(invalid_type(&t, expected)),
+        };
+        match integral(&number) {
+            Some(integer) if lenient => parse_integer(integer, expected),
+            _ => parse_integer(number, expected),
         }
     }
 
-    fn parse_null(&mut self) -> Result<()> {
-        let mut buf = [0; 4];
-        if let Some(hold) = self.hold {
-            buf[0] = hold;
-            self.input.read_exact(&mut buf[1..])?;
-        } else {
-            self.input.read_exact(&mut buf)?;
+    /// Parse a floating-point number
+    fn parse_float<V>(&mut self, expected: &dyn Expected) -> Result<V>
+    where
+        V: FromStr<Err = ParseFloatError> + Copy + Into<f64> + std::fmt::LowerExp,
+    {
+        let (number, quoted) = self.next_number(expected, self.coercion == Coercion::Lenient)?;
+        let v: V = number.parse()?;
+        if self.strict_numbers && v.into().is_infinite() && !number.contains('I') {
+            return Err(Error::NumberOutOfRange {
+                number,
+                ty: std::any::type_name::<V>(),
+            });
         }
-        if buf.as_slice() != b"null" {
-            Err(Error::Unexpected {
-                found: String::from_utf8(buf.to_vec())?,
-                expected: Some("null".to_string()),
-            })
-        } else {
-            Ok(())
+        // Coercing a string mustn't lose digits, so the float has to print back as the same number
+        if quoted && decimal(&number) != decimal(&format!("{v:e}")) {
+            return Err(serde::de::Error::invalid_value(
+                Unexpected::Str(&number),
+                expected,
+            ));
         }
+        Ok(v)
     }
 
-    fn parse_string(&mut self) -> Result<String> {
-        self.expect_next('"')?;
-
-        let mut buf = Vec::new();
-        loop {
-            self.input.read_until(b'"', &mut buf)?;
-            if buf.last() != Some(&b'"') {
-                return Err(Error::Unclosed('"'));
-            }
-            let len = buf.len();
-            let check_index = if len < 3 { len - 1 } else { len - 2 };
-            if buf[check_index] != b'\\' {
-                break;
-            }
+    /// Take a string, which may also be an object key
+    fn parse_string(&mut self, expected: &dyn Expected) -> Result<String> {
+        match self.next()? {
+            Token::String(s) | Token::Key(s) => Ok(s),
+            t => Err(invalid_type(&t, expected)),
         }
-        let s = String::from_utf8(buf)?;
-        unescape(&s[..(s.len() - 1)])
     }
 
-    fn parse_byte_buf(&mut self) -> Result<Vec<u8>> {
-        self.expect_next('[')?;
+    fn parse_byte_buf(&mut self, expected: &dyn Expected) -> Result<Vec<u8>> {
+        self.expect_next(Token::StartArray, expected)?;
         let mut buf = Vec::new();
         loop {
-            buf.push(self.parse_uint()?);
-            if self.peek()? == b']' {
-                self.next()?;
-                break;
+            match self.next()? {
+                Token::EndArray => break,
+                Token::Number(n) => buf.push(parse_integer(n, &"byte")?),
+                t => return Err(invalid_type(&t, &"byte")),
             }
-            self.expect_next(',')?;
         }
         Ok(buf)
     }
 }
 
+/// Forward each method to [`Raw`], pointing any error without a position at the last token taken
+macro_rules! located {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                let value = serde::Deserializer::$method(Raw(&mut *self), $($arg,)* visitor);
+                value.map_err(|e| self.locate(e))
+            }
+        )*
+    };
+}
+
 impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     type Error = Error;
 
+    located! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+}
+
+/// The deserializer itself, before errors are given a position
+struct Raw<'a, R: Read>(&'a mut Deserializer<R>);
+
+impl<'de, R: Read> serde::Deserializer<'de> for Raw<'_, R> {
+    type Error = Error;
+
     fn deserialize_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        match self.peek()? as char {
-            '"' => self.deserialize_str(visitor),
-            '[' => self.deserialize_seq(visitor),
-            '{' => self.deserialize_map(visitor),
-            'n' => self.deserialize_unit(visitor),
-            't' | 'f' => self.deserialize_bool(visitor),
-            '-' | '0'..='9' => self.parse_number(visitor),
-            c => Err(Error::Unexpected {
-                found: c.to_string(),
+        match self.0.next()? {
+            Token::String(s) | Token::Key(s) => visitor.visit_string(s),
+            Token::StartArray => {
+                let mut access = CommaSeparated::new(self.0);
+                let value = visitor.visit_seq(&mut access)?;
+                access.end(Token::EndArray)?;
+                Ok(value)
+            }
+            Token::StartObject => {
+                let mut access = CommaSeparated::new(self.0);
+                let value = visitor.visit_map(&mut access)?;
+                access.end(Token::EndObject)?;
+                Ok(value)
+            }
+            Token::Null => visitor.visit_unit(),
+            Token::Bool(b) => visitor.visit_bool(b),
+            Token::Number(n) => visit_number(n, self.0.strict_numbers, visitor),
+            t => Err(Error::Unexpected {
+                found: t.to_string(),
                 expected: None,
             }),
         }
@@ -230,30 +368,37 @@ impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_str(&self.parse_string()?)
+        let string = self.0.parse_string(&visitor)?;
+        visitor.visit_str(&string)
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.expect_next('[')?;
-        visitor.visit_seq(CommaSeparated::new(self))
+        self.0.expect_next(Token::StartArray, &visitor)?;
+        let mut access = CommaSeparated::new(self.0);
+        let value = visitor.visit_seq(&mut access)?;
+        access.end(Token::EndArray)?;
+        Ok(value)
     }
 
     fn deserialize_map<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.expect_next('{')?;
-        visitor.visit_map(CommaSeparated::new(self))
+        self.0.expect_next(Token::StartObject, &visitor)?;
+        let mut access = CommaSeparated::new(self.0);
+        let value = visitor.visit_map(&mut access)?;
+        access.end(Token::EndObject)?;
+        Ok(value)
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.parse_null()?;
+        self.0.expect_next(Token::Null, &visitor)?;
         visitor.visit_unit()
     }
 
@@ -261,37 +406,18 @@ impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     where
         V: Visitor<'de>,
     {
-        match self.peek()? as char {
-            't' => {
-                let mut buf = [0; 4];
-                buf[0] = b't';
-                self.input.read_exact(&mut buf[1..])?;
-                if buf.as_slice() == b"true" {
-                    visitor.visit_bool(true)
-                } else {
-                    Err(Error::Unexpected {
-                        found: String::from_utf8(buf.to_vec())?,
-                        expected: Some("true".to_string()),
-                    })
-                }
+        let lenient = self.0.coercion == Coercion::Lenient;
+        match self.0.next()? {
+            Token::Bool(b) => visitor.visit_bool(b),
+            Token::String(s) if lenient && (s == "true" || s == "false") => {
+                visitor.visit_bool(s == "true")
             }
-            'f' => {
-                let mut buf = [0; 5];
-                buf[0] = b'f';
-                self.input.read_exact(&mut buf[1..])?;
-                if buf.as_slice() == b"false" {
-                    visitor.visit_bool(false)
-                } else {
-                    Err(Error::Unexpected {
-                        found: String::from_utf8(buf.to_vec())?,
-                        expected: Some("false".to_string()),
-                    })
-                }
-            }
-            c => Err(Error::Unexpected {
-                found: c.to_string(),
-                expected: Some("true or false".to_string()),
-            }),
+            Token::Number(n) if lenient && (n == "0" || n == "1") => visitor.visit_bool(n == "1"),
+            Token::String(s) if lenient => Err(serde::de::Error::invalid_value(
+                Unexpected::Str(&s),
+                &visitor,
+            )),
+            t => Err(invalid_type(&t, &visitor)),
         }
     }
 
@@ -299,81 +425,107 @@ impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i8(self.parse_int()?)
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_i8(v)
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i16(self.parse_int()?)
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_i16(v)
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i32(self.parse_int()?)
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_i32(v)
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i64(self.parse_int()?)
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_i64(v)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_i128(v)
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u8(self.parse_uint()?)
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_u8(v)
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u16(self.parse_uint()?)
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_u16(v)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u32(self.parse_uint()?)
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_u32(v)
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.parse_uint()?)
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_u64(v)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.0.parse_int(&visitor)?;
+        visitor.visit_u128(v)
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_f32(self.parse_float()?)
+        let v = self.0.parse_float(&visitor)?;
+        visitor.visit_f32(v)
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_f64(self.parse_float()?)
+        let v = self.0.parse_float(&visitor)?;
+        visitor.visit_f64(v)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        if self.peek()? == b'n' {
-            self.parse_null()?;
+        if *self.0.peek()? == Token::Null {
+            self.0.next()?;
             visitor.visit_none()
         } else {
-            visitor.visit_some(self)
+            visitor.visit_some(self.0)
         }
     }
 
@@ -385,18 +537,24 @@ impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_unit(visitor)
+        self.0.deserialize_unit(visitor)
     }
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        #[cfg(feature = "arbitrary_precision")]
+        if name == crate::number::TOKEN {
+            let (number, _) = self.0.next_number(&visitor, false)?;
+            return visitor.visit_string(number);
+        }
+        let _ = name;
+        visitor.visit_newtype_struct(self.0)
     }
 
     fn deserialize_tuple<V>(
@@ -407,7 +565,7 @@ impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.0.deserialize_seq(visitor)
     }
 
     fn deserialize_tuple_struct<V>(
@@ -419,45 +577,52 @@ impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        self.0.deserialize_seq(visitor)
     }
 
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        self.0.expect_next(Token::StartObject, &visitor)?;
+        let mut access = CommaSeparated::new(self.0);
+        access.names = fields;
+        let value = visitor.visit_map(&mut access)?;
+        access.end(Token::EndObject)?;
+        Ok(value)
     }
 
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        match self.peek()? as char {
-            '"' => visitor.visit_enum(self.parse_string()?.into_deserializer()),
-            '{' => {
-                self.next()?;
-                let value = visitor.visit_enum(Enum(self))?;
-                if self.expect_next('}').is_err() {
-                    Err(Error::Unclosed('{'))
-                } else {
-                    Ok(value)
+        match self.0.next()? {
+            Token::String(s) => match self.0.resolve(&s, variants)? {
+                Some(variant) => visitor.visit_enum(variant.into_deserializer()),
+                None => visitor.visit_enum(s.into_deserializer()),
+            },
+            Token::StartObject => {
+                let value = visitor.visit_enum(Enum::new(&mut *self.0, variants))?;
+                // Only one variant may be given
+                match self.0.next()? {
+                    Token::EndObject => Ok(value),
+                    t => Err(Error::Unexpected {
+                        found: t.to_string(),
+                        expected: Some(Token::EndObject.to_string()),
+                    }),
                 }
             }
-            c => Err(Error::Unexpected {
-                found: c.to_string(),
-                expected: Some("string or object".to_string()),
-            }),
+            t => Err(invalid_type(&t, &visitor)),
         }
     }
 
@@ -465,14 +630,14 @@ impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     where
         V: Visitor<'de>,
     {
-        let string = self.parse_string()?;
-        if string.len() != 1 {
-            Err(Error::Unexpected {
-                found: string,
-                expected: Some("single character".to_string()),
-            })
-        } else {
-            visitor.visit_char(string.chars().next().unwrap())
+        let string = self.0.parse_string(&visitor)?;
+        let mut chars = string.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(serde::de::Error::invalid_value(
+                Unexpected::Str(&string),
+                &visitor,
+            )),
         }
     }
 
@@ -480,31 +645,70 @@ impl<'de, R: Read> serde::Deserializer<'de> for &mut Deserializer<R> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_byte_buf(self.parse_byte_buf()?)
+        let buf = self.0.parse_byte_buf(&visitor)?;
+        visitor.visit_byte_buf(buf)
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_bytes(&self.parse_byte_buf()?)
+        let buf = self.0.parse_byte_buf(&visitor)?;
+        visitor.visit_bytes(&buf)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let string = self.0.parse_string(&visitor)?;
+        visitor.visit_string(string)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_string(visitor)
     }
 
-    forward_to_deserialize_any! {string ignored_any identifier}
+    forward_to_deserialize_any! {ignored_any}
+}
+
+struct Enum<'a, R: Read> {
+    de: &'a mut Deserializer<R>,
+    /// The names of the enum's variants
+    variants: &'static [&'static str],
+    /// The variant name, for error paths
+    variant: String,
 }
 
-struct Enum<'a, R: Read>(pub &'a mut Deserializer<R>);
+impl<'a, R: Read> Enum<'a, R> {
+    fn new(de: &'a mut Deserializer<R>, variants: &'static [&'static str]) -> Self {
+        Self {
+            de,
+            variants,
+            variant: String::new(),
+        }
+    }
+}
 
 impl<'de, 'a, R: Read> serde::de::EnumAccess<'de> for Enum<'a, R> {
     type Error = Error;
     type Variant = Self;
 
-    fn variant_seed<V>(self, seed: V) -> std::result::Result<(V::Value, Self::Variant), Self::Error>
+    fn variant_seed<V>(
+        mut self,
+        seed: V,
+    ) -> std::result::Result<(V::Value, Self::Variant), Self::Error>
     where
         V: serde::de::DeserializeSeed<'de>,
     {
-        let val = seed.deserialize(&mut *self.0)?;
-        self.0.expect_next(':')?;
+        self.variant = self.de.parse_string(&"variant name")?;
+        let name = self.de.resolve(&self.variant, self.variants)?;
+        let val = seed.deserialize(MapKeyDeserializer {
+            key: name.unwrap_or(&self.variant),
+        })?;
         Ok((val, self))
     }
 }
@@ -513,45 +717,100 @@ impl<'de, 'a, R: Read> serde::de::VariantAccess<'de> for Enum<'a, R> {
     type Error = Error;
 
     fn unit_variant(self) -> std::result::Result<(), Self::Error> {
-        Err(Error::Message(
-            "Unit variant case should be handled by deserialize_enum".into(),
-        ))
+        serde::Deserialize::deserialize(&mut *self.de)
+            .map_err(|e| self.de.locate(e).within(Segment::Key(self.variant)))
     }
 
     fn newtype_variant_seed<T>(self, seed: T) -> std::result::Result<T::Value, Self::Error>
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        seed.deserialize(self.0)
+        seed.deserialize(&mut *self.de)
+            .map_err(|e| self.de.locate(e).within(Segment::Key(self.variant)))
     }
 
     fn tuple_variant<V>(self, _len: usize, visitor: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        serde::Deserializer::deserialize_seq(self.0, visitor)
+        serde::Deserializer::deserialize_seq(&mut *self.de, visitor)
+            .map_err(|e| self.de.locate(e).within(Segment::Key(self.variant)))
     }
 
     fn struct_variant<V>(
         self,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> std::result::Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        serde::Deserializer::deserialize_map(self.0, visitor)
+        // As a struct, so its keys are matched against the fields
+        serde::Deserializer::deserialize_struct(&mut *self.de, "", fields, visitor)
+            .map_err(|e| self.de.locate(e).within(Segment::Key(self.variant)))
     }
 }
 
 struct CommaSeparated<'a, R: Read> {
     de: &'a mut Deserializer<R>,
-    start: bool,
+    /// The number of elements or entries read so far, which is the index of the next element
+    /// for error paths
+    index: usize,
+    /// The current key, for error paths
+    key: String,
+    /// Whether the closing token has been consumed
+    done: bool,
+    /// The field names keys are matched against, when deserializing a struct
+    names: &'static [&'static str],
 }
 
 impl<'a, R: Read> CommaSeparated<'a, R> {
     fn new(de: &'a mut Deserializer<R>) -> Self {
-        Self { de, start: true }
+        Self {
+            de,
+            index: 0,
+            key: String::new(),
+            done: false,
+            names: &[],
+        }
+    }
+
+    /// Check for another entry, consuming `close` if there are none left
+    fn has_next(&mut self, close: Token) -> Result<bool> {
+        if self.done {
+            return Ok(false);
+        }
+        if *self.de.peek()? == close {
+            self.de.next()?;
+            self.done = true;
+            Ok(false)
+        } else {
+            Ok(true)
+        }
+    }
+
+    /// Consume `close` once the visitor is finished, rejecting leftover entries
+    fn end(&mut self, close: Token) -> Result<()> {
+        if !self.has_next(close.clone())? {
+            return Ok(());
+        }
+        let position = match self.de.tokens.peek_event()? {
+            Some(event) => event.position,
+            None => return Err(Error::Eof),
+        };
+        // Skip the leftovers to count them
+        let read = self.index;
+        let expected = if close == Token::EndArray {
+            while self.next_element::<IgnoredAny>()?.is_some() {}
+            format!("{read} elements")
+        } else {
+            while self.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+            format!("{read} entries")
+        };
+        Err(
+            <Error as serde::de::Error>::invalid_length(self.index, &expected.as_str())
+                .at_position(position),
+        )
     }
 }
 
@@ -565,17 +824,14 @@ impl<'a, 'de, R: Read> SeqAccess<'de> for CommaSeparated<'a, R> {
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        if self.de.peek()? == b']' {
-            self.de.next()?;
+        if !self.has_next(Token::EndArray)? {
             return Ok(None);
         }
-
-        if !self.start {
-            self.de.expect_next(',')?;
-        } else {
-            self.start = false;
-        }
-        seed.deserialize(&mut *self.de).map(Some)
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(&mut *self.de)
+            .map(Some)
+            .map_err(|e| self.de.locate(e).within(Segment::Index(index)))
     }
 }
 
@@ -586,77 +842,401 @@ impl<'a, 'de, R: Read> MapAccess<'de> for CommaSeparated<'a, R> {
     where
         K: serde::de::DeserializeSeed<'de>,
     {
-        if self.de.peek()? == b'}' {
-            self.de.next()?;
+        if !self.has_next(Token::EndObject)? {
             return Ok(None);
         }
-
-        if !self.start {
-            self.de.expect_next(',')?;
-        } else {
-            self.start = false;
-        }
-        seed.deserialize(&mut *self.de).map(Some)
+        self.index += 1;
+        self.key = self.de.parse_string(&"object key")?;
+        let name = self
+            .de
+            .resolve(&self.key, self.names)
+            .map_err(|e| self.de.locate(e).within(Segment::Key(self.key.clone())))?;
+        seed.deserialize(MapKeyDeserializer {
+            key: name.unwrap_or(&self.key),
+        })
+        .map(Some)
+        .map_err(|e| self.de.locate(e).within(Segment::Key(self.key.clone())))
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> std::result::Result<V::Value, Self::Error>
     where
         V: serde::de::DeserializeSeed<'de>,
     {
-        self.de.expect_next(':')?;
         seed.deserialize(&mut *self.de)
+            .map_err(|e| self.de.locate(e).within(Segment::Key(self.key.clone())))
+    }
+}
+
+/// Deserializes object keys, which may also stand for numbers, bools and unit variants
+struct MapKeyDeserializer<'a> {
+    key: &'a str,
+}
+
+impl MapKeyDeserializer<'_> {
+    fn parse<V: FromStr>(&self, expected: &dyn Expected) -> Result<V> {
+        self.key
+            .parse()
+            .map_err(|_| serde::de::Error::invalid_value(Unexpected::Str(self.key), expected))
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for MapKeyDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_str(self.key)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_bool(v)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_i8(v)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_i16(v)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_i32(v)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_i64(v)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_i128(v)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_u8(v)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_u16(v)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_u32(v)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_u64(v)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let v = self.parse(&visitor)?;
+        visitor.visit_u128(v)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.key.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        f32 f64 char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
     }
 }
 
 pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T> {
     let mut de = Deserializer::new(s.as_bytes());
-    let t = T::deserialize(&mut de)?;
-    Ok(t)
+    T::deserialize(&mut de)
 }
 
 pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
     let mut de = Deserializer::new(bytes);
-    let t = T::deserialize(&mut de)?;
-    Ok(t)
+    T::deserialize(&mut de)
+}
+
+pub fn from_str_jsonc<T: DeserializeOwned>(s: &str) -> Result<T> {
+    let mut de = Deserializer::new(s.as_bytes()).syntax(Syntax::Jsonc);
+    T::deserialize(&mut de)
+}
+
+pub fn from_str_json5<T: DeserializeOwned>(s: &str) -> Result<T> {
+    let mut de = Deserializer::new(s.as_bytes()).syntax(Syntax::Json5);
+    T::deserialize(&mut de)
 }
 
 pub fn from_reader<T: DeserializeOwned>(reader: &mut impl Read) -> Result<T> {
     let mut de = Deserializer::new(reader);
-    let t = T::deserialize(&mut de)?;
-    Ok(t)
-}
-
-fn unescape(s: &str) -> Result<String> {
-    let mut out = String::new();
-    let mut chars = s.chars();
-    while let Some(c) = chars.next() {
-        if c == '\\' {
-            let c = chars.next().unwrap();
-            match c {
-                '"' => out.push('"'),
-                '\\' => out.push('\\'),
-                'b' => out.push('\x08'),
-                'f' => out.push('\x0c'),
-                'n' => out.push('\n'),
-                'r' => out.push('\r'),
-                't' => out.push('\t'),
-                'u' => {
-                    let mut buf = ['\0'; 2];
-                    chars.next();
-                    chars.next();
-                    for item in &mut buf {
-                        *item = chars.next().ok_or(Error::InvalidEscape)?;
-                    }
-                    out.push(
-                        char::from_u32(u32::from_str_radix(&buf.iter().collect::<String>(), 16)?)
-                            .ok_or(Error::InvalidEscape)?,
-                    );
-                }
-                _ => out.push(c),
+    T::deserialize(&mut de)
+}
+
+/// Deserialize an instance of `T` from an async reader.
+///
+/// Input is read in chunks and handed to a parser running on tokio's blocking thread pool as it
+/// arrives, so the document is never buffered in full. Parsing finishes as soon as a complete value
+/// has been read, even if the reader is still open.
+#[cfg(feature = "async")]
+pub async fn from_async_reader<T>(mut reader: impl tokio::io::AsyncRead + Unpin) -> Result<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    use tokio::io::AsyncReadExt;
+
+    let (tx, rx) = tokio::sync::mpsc::channel(ASYNC_CHANNEL_CAPACITY);
+    let mut parse = tokio::task::spawn_blocking(move || {
+        from_reader(&mut ChannelReader {
+            rx,
+            chunk: std::io::Cursor::new(Vec::new()),
+        })
+    });
+
+    let pump = async move {
+        let mut buf = vec![0; ASYNC_CHUNK_SIZE];
+        loop {
+            let chunk = match reader.read(&mut buf).await {
+                Ok(0) => break,
+                Ok(n) => Ok(buf[..n].to_vec()),
+                Err(e) => Err(e),
+            };
+            let failed = chunk.is_err();
+            // The receiver is only dropped once the parser is done with the input
+            if tx.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+    };
+
+    let joined = tokio::select! {
+        joined = &mut parse => joined,
+        () = pump => parse.await,
+    };
+    joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// A blocking reader over chunks sent from an async task
+#[cfg(feature = "async")]
+struct ChannelReader {
+    rx: tokio::sync::mpsc::Receiver<std::io::Result<Vec<u8>>>,
+    chunk: std::io::Cursor<Vec<u8>>,
+}
+
+#[cfg(feature = "async")]
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.chunk.position() as usize == self.chunk.get_ref().len() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => self.chunk = std::io::Cursor::new(chunk?),
+                None => return Ok(0),
             }
-        } else {
-            out.push(c);
         }
+        self.chunk.read(buf)
     }
-    Ok(out)
+}
+
+/// Parse an integer literal, telling apart literals that are out of range and ones that aren't
+/// integers at all
+fn parse_integer<V: FromStr>(number: String, expected: &dyn Expected) -> Result<V> {
+    // Unsigned types don't parse `-0`, though it's zero all the same
+    let digits = if number == "-0" { "0" } else { &number };
+    match digits.parse() {
+        Ok(v) => Ok(v),
+        Err(_) if number.contains(['.', 'e', 'E', 'I', 'N']) => {
+            Err(invalid_type(&Token::Number(number), expected))
+        }
+        Err(_) => Err(Error::NumberOutOfRange {
+            number,
+            ty: std::any::type_name::<V>(),
+        }),
+    }
+}
+
+/// A number's sign, significant digits and exponent, such that its value is
+/// `0.{digits} × 10^{exponent}` and `1.50e2` and `150` compare equal
+fn decimal(text: &str) -> Option<(bool, String, i64)> {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, text),
+    };
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (unsigned, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{int}{frac}");
+    let significant = digits.trim_start_matches('0');
+    let leading_zeros = digits.len() - significant.len();
+    let exponent = exponent
+        .checked_add(int.len() as i64)?
+        .checked_sub(leading_zeros as i64)?;
+    match significant.trim_end_matches('0') {
+        "" => Some((negative, String::new(), 0)),
+        significant => Some((negative, significant.to_string(), exponent)),
+    }
+}
+
+/// The integer a number literal with a fraction or exponent stands for, if it has no fractional
+/// part, such as `5` for `5.0` or `1500` for `1.5e3`
+fn integral(number: &str) -> Option<String> {
+    if !number.contains(['.', 'e', 'E']) {
+        return None;
+    }
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", number),
+    };
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (unsigned, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let mut digits = format!("{int}{frac}");
+    // Where the decimal point falls in `digits`; no integer type holds more than 40 places
+    let point = (int.len() as i64).checked_add(exponent)?;
+    if point > 40 {
+        return None;
+    }
+    let point = point.max(0) as usize;
+    if point > digits.len() {
+        digits += &"0".repeat(point - digits.len());
+    }
+    let (whole, fraction) = digits.split_at(point);
+    if fraction.bytes().any(|b| b != b'0') {
+        return None;
+    }
+    match whole.trim_start_matches('0') {
+        "" => Some("0".to_string()),
+        whole => Some(format!("{sign}{whole}")),
+    }
+}
+
+/// Visit a number literal as an i64, u64, i128, u128, or f64
+///
+/// Integers use the narrowest type that fits. Those too large for 128 bits, and floats too large
+/// for an f64, are rounded to an f64 unless `strict` is set.
+fn visit_number<'de, V: Visitor<'de>>(
+    string: String,
+    strict: bool,
+    visitor: V,
+) -> Result<V::Value> {
+    if string.contains(['.', 'e', 'E', 'I', 'N']) {
+        let f: f64 = string.parse()?;
+        if strict && f.is_infinite() && !string.contains('I') {
+            return Err(Error::NumberOutOfRange {
+                number: string,
+                ty: "f64",
+            });
+        }
+        visitor.visit_f64(f)
+    } else if let Ok(n) = string.parse() {
+        visitor.visit_u64(n)
+    } else if let Ok(n) = string.parse() {
+        visitor.visit_i64(n)
+    } else if let Ok(n) = string.parse() {
+        visitor.visit_u128(n)
+    } else if let Ok(n) = string.parse() {
+        visitor.visit_i128(n)
+    } else if strict {
+        let ty = if string.starts_with('-') {
+            "i128"
+        } else {
+            "u128"
+        };
+        Err(Error::NumberOutOfRange { number: string, ty })
+    } else {
+        visitor.visit_f64(string.parse()?)
+    }
+}
+
+/// The error for a token that doesn't fit the type being deserialized
+fn invalid_type(token: &Token, expected: &dyn Expected) -> Error {
+    let unexpected = match token {
+        Token::String(s) | Token::Key(s) => Unexpected::Str(s),
+        Token::Number(n) => {
+            if let Ok(n) = n.parse() {
+                Unexpected::Unsigned(n)
+            } else if let Ok(n) = n.parse() {
+                Unexpected::Signed(n)
+            } else if let Ok(n) = n.parse() {
+                Unexpected::Float(n)
+            } else {
+                Unexpected::Other("number")
+            }
+        }
+        Token::Bool(b) => Unexpected::Bool(*b),
+        Token::Null => Unexpected::Unit,
+        Token::StartArray => Unexpected::Seq,
+        Token::StartObject => Unexpected::Map,
+        Token::EndArray => Unexpected::Other("end of array"),
+        Token::EndObject => Unexpected::Other("end of object"),
+    };
+    serde::de::Error::invalid_type(unexpected, expected)
 }
@@ -2,6 +2,8 @@ use std::num::ParseIntError;
 
 use serde::{de, ser};
 
+use crate::tokenizer::Position;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("IO error")]
@@ -10,10 +12,14 @@ pub enum Error {
         #[source]
         std::io::Error,
     ),
-    #[error("Key is not a string")]
+    #[error("Key cannot be written as a string")]
     KeyNotString,
+    #[error("Unexpected end of input")]
+    Eof,
     #[error("Unclosed delimiter {0}")]
     Unclosed(char),
+    #[error("Trailing comma before {0}")]
+    TrailingComma(char),
     #[error("Failed to read UTF-8")]
     Utf8(
         #[from]
@@ -40,8 +46,423 @@ pub enum Error {
         std::num::ParseFloatError,
     ),
 
+    #[error("Number {number} is out of range for {ty}")]
+    NumberOutOfRange { number: String, ty: &'static str },
+
+    #[error("Cannot write {found} here (expected {expected})")]
+    Misplaced {
+        found: &'static str,
+        expected: &'static str,
+    },
+
+    #[error("Invalid type: {unexpected}, expected {expected}")]
+    InvalidType {
+        unexpected: Unexpected,
+        expected: String,
+    },
+    #[error("Invalid value: {unexpected}, expected {expected}")]
+    InvalidValue {
+        unexpected: Unexpected,
+        expected: String,
+    },
+    #[error("Invalid length {len}, expected {expected}")]
+    InvalidLength { len: usize, expected: String },
+    #[error(
+        "Unknown variant `{variant}`, {}{}",
+        one_of("variants", expected),
+        did_you_mean(suggestion)
+    )]
+    UnknownVariant {
+        variant: String,
+        expected: &'static [&'static str],
+        /// The expected variant closest to `variant`
+        suggestion: Option<&'static str>,
+    },
+    #[error(
+        "Unknown field `{field}`, {}{}",
+        one_of("fields", expected),
+        did_you_mean(suggestion)
+    )]
+    UnknownField {
+        field: String,
+        expected: &'static [&'static str],
+        /// The expected field closest to `field`
+        suggestion: Option<&'static str>,
+    },
+    #[error(
+        "`{name}` is ambiguous, it could be any of {}",
+        one_of_list(candidates)
+    )]
+    AmbiguousName {
+        name: String,
+        /// The expected names `name` matches
+        candidates: Vec<&'static str>,
+    },
+    #[error("Missing field `{0}`")]
+    MissingField(&'static str),
+    #[error("Duplicate field `{0}`")]
+    DuplicateField(&'static str),
+
     #[error("{0}")]
     Message(String),
+
+    #[error("{source}{}", location(path, position))]
+    At {
+        path: Path,
+        position: Option<Position>,
+        #[source]
+        source: Box<Error>,
+    },
+}
+
+fn location(path: &Path, position: &Option<Position>) -> String {
+    let mut out = String::new();
+    if !path.0.is_empty() {
+        out += &format!(" at {path}");
+    }
+    if let Some(position) = position {
+        out += &format!(" ({position})");
+    }
+    out
+}
+
+/// An owned copy of [`serde::de::Unexpected`], describing a value that didn't fit
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unexpected {
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    Char(char),
+    Str(String),
+    Bytes(Vec<u8>),
+    Unit,
+    Option,
+    NewtypeStruct,
+    Seq,
+    Map,
+    Enum,
+    UnitVariant,
+    NewtypeVariant,
+    TupleVariant,
+    StructVariant,
+    Other(String),
+}
+
+impl From<de::Unexpected<'_>> for Unexpected {
+    fn from(unexpected: de::Unexpected<'_>) -> Self {
+        match unexpected {
+            de::Unexpected::Bool(b) => Self::Bool(b),
+            de::Unexpected::Unsigned(n) => Self::Unsigned(n),
+            de::Unexpected::Signed(n) => Self::Signed(n),
+            de::Unexpected::Float(n) => Self::Float(n),
+            de::Unexpected::Char(c) => Self::Char(c),
+            de::Unexpected::Str(s) => Self::Str(s.to_string()),
+            de::Unexpected::Bytes(b) => Self::Bytes(b.to_vec()),
+            de::Unexpected::Unit => Self::Unit,
+            de::Unexpected::Option => Self::Option,
+            de::Unexpected::NewtypeStruct => Self::NewtypeStruct,
+            de::Unexpected::Seq => Self::Seq,
+            de::Unexpected::Map => Self::Map,
+            de::Unexpected::Enum => Self::Enum,
+            de::Unexpected::UnitVariant => Self::UnitVariant,
+            de::Unexpected::NewtypeVariant => Self::NewtypeVariant,
+            de::Unexpected::TupleVariant => Self::TupleVariant,
+            de::Unexpected::StructVariant => Self::StructVariant,
+            de::Unexpected::Other(other) => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Unexpected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unexpected = match self {
+            Self::Bool(b) => de::Unexpected::Bool(*b),
+            Self::Unsigned(n) => de::Unexpected::Unsigned(*n),
+            Self::Signed(n) => de::Unexpected::Signed(*n),
+            Self::Float(n) => de::Unexpected::Float(*n),
+            Self::Char(c) => de::Unexpected::Char(*c),
+            Self::Str(s) => de::Unexpected::Str(s),
+            Self::Bytes(b) => de::Unexpected::Bytes(b),
+            Self::Unit => de::Unexpected::Unit,
+            Self::Option => de::Unexpected::Option,
+            Self::NewtypeStruct => de::Unexpected::NewtypeStruct,
+            Self::Seq => de::Unexpected::Seq,
+            Self::Map => de::Unexpected::Map,
+            Self::Enum => de::Unexpected::Enum,
+            Self::UnitVariant => de::Unexpected::UnitVariant,
+            Self::NewtypeVariant => de::Unexpected::NewtypeVariant,
+            Self::TupleVariant => de::Unexpected::TupleVariant,
+            Self::StructVariant => de::Unexpected::StructVariant,
+            Self::Other(other) => de::Unexpected::Other(other),
+        };
+        unexpected.fmt(f)
+    }
+}
+
+/// Describe the allowed names for an unknown field or variant
+fn one_of(kind: &str, names: &[&str]) -> String {
+    match names {
+        [] => format!("there are no {kind}"),
+        [name] => format!("expected `{name}`"),
+        names => format!("expected one of {}", one_of_list(names)),
+    }
+}
+
+fn one_of_list(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn did_you_mean(suggestion: &Option<&str>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(" (did you mean `{suggestion}`?)"),
+        None => String::new(),
+    }
+}
+
+/// Find the candidate closest to `name`, if any is close enough to be a likely typo
+fn closest(name: &str, candidates: &'static [&'static str]) -> Option<&'static str> {
+    let threshold = (name.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of insertions, deletions, substitutions and adjacent transpositions needed to turn
+/// `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // Rows for the previous two prefixes of `a`, to allow for transpositions
+    let mut before = vec![0; b.len() + 1];
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+/// Where in a document an error happened, such as `servers[3].port`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Path(Vec<Segment>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// An object key or enum variant
+    Key(String),
+    /// An array index
+    Index(usize),
+}
+
+impl Path {
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Key(key) if i == 0 => write!(f, "{key}")?,
+                Segment::Key(key) => write!(f, ".{key}")?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The broad kind of an [`Error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// Reading or writing failed
+    Io,
+    /// The input isn't valid JSON
+    Syntax,
+    /// The data doesn't fit the type it's being converted to or from
+    Data,
+    /// The input ended before a complete value was read
+    Eof,
+}
+
+impl Error {
+    /// Where in the document the error happened, if it wasn't at the top level
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::At { path, .. } if !path.0.is_empty() => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Where in the input the error happened
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Self::At { position, .. } => *position,
+            _ => None,
+        }
+    }
+
+    /// The error without its location
+    pub fn inner(&self) -> &Error {
+        match self {
+            Self::At { source, .. } => source,
+            e => e,
+        }
+    }
+
+    /// Record that the error happened inside `segment`
+    pub(crate) fn within(self, segment: Segment) -> Self {
+        match self {
+            Self::At {
+                mut path,
+                position,
+                source,
+            } => {
+                path.0.insert(0, segment);
+                Self::At {
+                    path,
+                    position,
+                    source,
+                }
+            }
+            source => Self::At {
+                path: Path(vec![segment]),
+                position: None,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Record where in the input the error happened, unless that's already known
+    pub(crate) fn at_position(self, at: Position) -> Self {
+        match self {
+            Self::At {
+                path,
+                position,
+                source,
+            } => Self::At {
+                path,
+                position: position.or(Some(at)),
+                source,
+            },
+            source => Self::At {
+                path: Path::default(),
+                position: Some(at),
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Render the error like a compiler diagnostic, pointing at the offending part of `source`.
+    ///
+    /// `source` should be the input the error came from.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = format!("error: {}\n", self.inner());
+        if let Some(position) = self.position() {
+            let number = position.line.to_string();
+            let gutter = " ".repeat(number.len());
+            out += &format!("{gutter}--> {position}\n");
+            if let Some(line) = source.lines().nth(position.line - 1) {
+                // Keep tabs so the caret lines up with the text above it
+                let indent = line
+                    .chars()
+                    .take(position.column - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect::<String>();
+                out += &format!("{gutter} |\n{number} | {line}\n{gutter} | {indent}^\n");
+            }
+        }
+        if let Some(path) = self.path() {
+            out += &format!("  = path: {path}\n");
+        }
+        if let Some(hint) = self.inner().hint() {
+            out += &format!("  = hint: {hint}\n");
+        }
+        out
+    }
+
+    /// A suggestion for fixing the error, for [`render`](Self::render)
+    fn hint(&self) -> Option<String> {
+        match self {
+            Self::Eof => Some("the input ends early; check for a missing `}`, `]` or `\"`".into()),
+            Self::TrailingComma(_) => Some("remove the trailing comma".into()),
+            Self::Unexpected { found, expected } => match (found.as_str(), expected.as_deref()) {
+                ("'", _) => Some("strings and keys must use double quotes".into()),
+                (_, Some(",")) => Some("add a `,` between entries, or close the container".into()),
+                (_, Some(":")) => Some("add a `:` between the key and its value".into()),
+                (_, Some("\"")) => Some("object keys must be strings".into()),
+                _ => None,
+            },
+            Self::InvalidEscape => Some(
+                "valid escapes are \\\", \\\\, \\/, \\b, \\f, \\n, \\r, \\t and \\uXXXX".into(),
+            ),
+            Self::MissingField(field) => Some(format!("add a value for `{field}`")),
+            // Unknown names already carry their suggestion in the message
+            _ => None,
+        }
+    }
+
+    pub fn classify(&self) -> Category {
+        match self {
+            Self::At { source, .. } => source.classify(),
+            Self::Io(_) => Category::Io,
+            Self::Eof => Category::Eof,
+            Self::Unclosed(_)
+            | Self::TrailingComma(_)
+            | Self::Utf8(_)
+            | Self::Unexpected { .. }
+            | Self::InvalidEscape => Category::Syntax,
+            // The tokenizer has already checked the syntax of number literals
+            Self::ParseInt(_)
+            | Self::ParseFloat(_)
+            | Self::NumberOutOfRange { .. }
+            | Self::KeyNotString
+            | Self::Misplaced { .. }
+            | Self::InvalidType { .. }
+            | Self::InvalidValue { .. }
+            | Self::InvalidLength { .. }
+            | Self::UnknownVariant { .. }
+            | Self::UnknownField { .. }
+            | Self::AmbiguousName { .. }
+            | Self::MissingField(_)
+            | Self::DuplicateField(_)
+            | Self::Message(_) => Category::Data,
+        }
+    }
+
+    pub fn is_io(&self) -> bool {
+        self.classify() == Category::Io
+    }
+
+    pub fn is_syntax(&self) -> bool {
+        self.classify() == Category::Syntax
+    }
+
+    pub fn is_data(&self) -> bool {
+        self.classify() == Category::Data
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.classify() == Category::Eof
+    }
 }
 
 impl ser::Error for Error {
@@ -60,6 +481,51 @@ impl de::Error for Error {
     {
         Self::Message(msg.to_string())
     }
+
+    fn invalid_type(unexp: de::Unexpected, exp: &dyn de::Expected) -> Self {
+        Self::InvalidType {
+            unexpected: unexp.into(),
+            expected: exp.to_string(),
+        }
+    }
+
+    fn invalid_value(unexp: de::Unexpected, exp: &dyn de::Expected) -> Self {
+        Self::InvalidValue {
+            unexpected: unexp.into(),
+            expected: exp.to_string(),
+        }
+    }
+
+    fn invalid_length(len: usize, exp: &dyn de::Expected) -> Self {
+        Self::InvalidLength {
+            len,
+            expected: exp.to_string(),
+        }
+    }
+
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        Self::UnknownVariant {
+            variant: variant.to_string(),
+            expected,
+            suggestion: closest(variant, expected),
+        }
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Self::UnknownField {
+            field: field.to_string(),
+            expected,
+            suggestion: closest(field, expected),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::MissingField(field)
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        Self::DuplicateField(field)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
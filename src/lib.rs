@@ -1,6 +1,32 @@
+pub mod case;
+pub use case::KeyCase;
 pub mod de;
-pub use de::{Deserializer, from_bytes, from_reader, from_str};
+#[cfg(feature = "async")]
+pub use de::from_async_reader;
+pub use de::{
+    Coercion, Deserializer, NameMatching, Syntax, from_bytes, from_reader, from_str,
+    from_str_json5, from_str_jsonc,
+};
 pub mod ser;
-pub use ser::{Serializer, to_bytes, to_string, to_writer};
+#[cfg(feature = "async")]
+pub use ser::to_async_writer;
+pub use ser::{IntegerFormat, Serializer, to_bytes, to_string, to_string_projected, to_writer};
+pub mod redact;
+pub use redact::Redactor;
+pub mod tokenizer;
+pub use tokenizer::{Token, Tokenizer};
+#[cfg(feature = "arbitrary_precision")]
+pub mod number;
+#[cfg(feature = "arbitrary_precision")]
+pub use number::Number;
+pub mod writer;
+pub use writer::JsonWriter;
 pub mod error;
-pub use error::{Error, Result};
+pub use error::{Category, Error, Result};
+
+/// The most bytes the async adapters move between their async and blocking halves at once
+#[cfg(feature = "async")]
+const ASYNC_CHUNK_SIZE: usize = 8 * 1024;
+/// How many chunks the async adapters let their blocking half get ahead by
+#[cfg(feature = "async")]
+const ASYNC_CHANNEL_CAPACITY: usize = 4;
@@ -0,0 +1,172 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Error;
+use crate::tokenizer::is_number_literal;
+
+/// The newtype struct name our serializer and deserializer recognise as a [`Number`]
+pub(crate) const TOKEN: &str = "$serde_json_exercise::Number";
+
+/// A JSON number that keeps the exact text of its literal.
+///
+/// Deserializing into a `Number` never rounds, and serializing one writes the literal back
+/// verbatim. The exception is a `Number` inside an untagged enum, internally tagged enum or
+/// flattened struct, which serde buffers as a primitive first, so a fraction that an `f64` can't
+/// hold arrives rounded. The `as_*` methods convert to primitives, returning `None` when the
+/// value doesn't fit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Number {
+    text: String,
+}
+
+macro_rules! as_int {
+    ($($method:ident: $ty:ty),*) => {
+        $(
+            #[doc = concat!("The value as a `", stringify!($ty), "`, if it is an integer in range")]
+            pub fn $method(&self) -> Option<$ty> {
+                self.text.parse().ok()
+            }
+        )*
+    };
+}
+
+impl Number {
+    /// The literal as it appeared in the document
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether the literal has no fraction or exponent
+    pub fn is_integer(&self) -> bool {
+        !self.text.contains(['.', 'e', 'E'])
+    }
+
+    as_int! {
+        as_i8: i8, as_i16: i16, as_i32: i32, as_i64: i64, as_i128: i128,
+        as_u8: u8, as_u16: u16, as_u32: u32, as_u64: u64, as_u128: u128
+    }
+
+    /// The nearest `f64`, if the value is within its range
+    pub fn as_f64(&self) -> Option<f64> {
+        self.text.parse().ok().filter(|f: &f64| f.is_finite())
+    }
+
+    /// The nearest `f32`, if the value is within its range
+    pub fn as_f32(&self) -> Option<f32> {
+        self.text.parse().ok().filter(|f: &f32| f.is_finite())
+    }
+}
+
+impl FromStr for Number {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.strip_prefix('+').unwrap_or(s);
+        let (sign, unsigned) = match text.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", text),
+        };
+        // JSON5 lets one side of the decimal point go without digits, as in `.5` and `5.`
+        let unsigned = match unsigned.find('.') {
+            Some(0) => format!("0{unsigned}"),
+            Some(point) if !unsigned[point + 1..].starts_with(|c: char| c.is_ascii_digit()) => {
+                format!("{}.0{}", &unsigned[..point], &unsigned[point + 1..])
+            }
+            _ => unsigned.to_string(),
+        };
+        let text = format!("{sign}{unsigned}");
+        if is_number_literal(&text) {
+            Ok(Self { text })
+        } else {
+            Err(Error::Unexpected {
+                found: s.to_string(),
+                expected: Some("number".to_string()),
+            })
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+macro_rules! from_int {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(n: $ty) -> Self {
+                    Self {
+                        text: itoa::Buffer::new().format(n).to_string(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_int!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128);
+
+impl Serialize for Number {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(TOKEN, &self.text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Number {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(TOKEN, NumberVisitor)
+    }
+}
+
+struct NumberVisitor;
+
+impl<'de> Visitor<'de> for NumberVisitor {
+    type Value = Number;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a finite number")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Number, E> {
+        v.parse()
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Number, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Number, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Number, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<Number, E> {
+        Ok(v.into())
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Number, E> {
+        if v.is_finite() {
+            Ok(Number {
+                text: v.to_string(),
+            })
+        } else {
+            Err(E::invalid_value(Unexpected::Float(v), &self))
+        }
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Number, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
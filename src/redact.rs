@@ -0,0 +1,183 @@
+use crate::error::Segment;
+
+/// Which values a [`Serializer`](crate::Serializer) masks, for logging documents that contain
+/// secrets.
+///
+/// Rules match object keys and struct fields by name, by glob, or by JSON Pointer. A matching
+/// value is written as `"[REDACTED]"`, or as a keyed hash of the value with
+/// [`hash`](Self::hash), so everything beneath it is hidden too.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    rules: Vec<Rule>,
+    /// The key to hash masked values with, if they're hashed
+    hash: Option<[u8; 16]>,
+}
+
+#[derive(Debug, Clone)]
+enum Rule {
+    /// A key with this name, anywhere in the document
+    Field(String),
+    /// A key matching this pattern, anywhere in the document
+    Glob(String),
+    /// The value at exactly this path
+    Pointer(Vec<String>),
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            hash: None,
+        }
+    }
+
+    /// Mask every value under a key called `name`
+    pub fn field(mut self, name: &str) -> Self {
+        self.rules.push(Rule::Field(name.to_string()));
+        self
+    }
+
+    /// Mask every value under a key matching `pattern`, where `*` matches any run of characters
+    /// and `?` any one character
+    pub fn glob(mut self, pattern: &str) -> Self {
+        self.rules.push(Rule::Glob(pattern.to_string()));
+        self
+    }
+
+    /// Mask the value at a JSON Pointer such as `/payment/card`, where array elements are
+    /// numbered from 0
+    pub fn pointer(mut self, pointer: &str) -> Self {
+        let segments = pointer
+            .split('/')
+            .skip(1)
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        self.rules.push(Rule::Pointer(segments));
+        self
+    }
+
+    /// Write masked values as a hash instead, so equal values can still be told apart
+    ///
+    /// Values are hashed with SipHash-2-4 under `key`, so the same key gives the same hashes
+    /// across runs and builds. Without the key, a hash can't be checked against guesses such as
+    /// common passwords, so keep it secret and out of the logs it protects.
+    pub fn hash(mut self, key: [u8; 16]) -> Self {
+        self.hash = Some(key);
+        self
+    }
+
+    /// Whether the value at `path` should be masked
+    pub(crate) fn matches(&self, path: &[Segment]) -> bool {
+        let key = match path.last() {
+            Some(Segment::Key(key)) => Some(key.as_str()),
+            _ => None,
+        };
+        self.rules.iter().any(|rule| match rule {
+            Rule::Field(name) => key == Some(name),
+            Rule::Glob(pattern) => key.is_some_and(|key| glob(pattern, key)),
+            Rule::Pointer(segments) => {
+                segments.len() == path.len()
+                    && segments.iter().zip(path).all(|(segment, at)| match at {
+                        Segment::Key(key) => segment == key,
+                        Segment::Index(index) => *segment == index.to_string(),
+                    })
+            }
+        })
+    }
+
+    /// The text to write in place of a masked value, given the value as JSON
+    pub(crate) fn replacement(&self, json: &[u8]) -> String {
+        match &self.hash {
+            Some(key) => format!("{:016x}", siphash(key, json)),
+            None => "[REDACTED]".to_string(),
+        }
+    }
+
+    /// Whether writing a replacement needs the masked value
+    pub(crate) fn hashes(&self) -> bool {
+        self.hash.is_some()
+    }
+}
+
+/// Match `text` against a pattern of literal characters, `*` and `?`
+fn glob(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    // Where to resume after the last `*`, if the match so far fails
+    let mut star = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// SipHash-2-4, a keyed hash that's stable across builds and platforms
+fn siphash(key: &[u8; 16], bytes: &[u8]) -> u64 {
+    let word = |bytes: &[u8]| {
+        let mut word = [0; 8];
+        word[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(word)
+    };
+    let (k0, k1) = (word(&key[..8]), word(&key[8..]));
+    let mut v = [
+        k0 ^ 0x736f6d6570736575,
+        k1 ^ 0x646f72616e646f6d,
+        k0 ^ 0x6c7967656e657261,
+        k1 ^ 0x7465646279746573,
+    ];
+    let mut compress = |m: u64| {
+        v[3] ^= m;
+        sip_round(&mut v);
+        sip_round(&mut v);
+        v[0] ^= m;
+    };
+    let chunks = bytes.chunks_exact(8);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        compress(word(chunk));
+    }
+    // The last word holds the leftover bytes and the length's low byte
+    compress(word(tail) | ((bytes.len() as u64) << 56));
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
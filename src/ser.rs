@@ -7,11 +7,41 @@ use serde::{
 };
 use std::io::Write;
 
-use crate::{Error, Result};
+#[cfg(feature = "async")]
+use crate::{ASYNC_CHANNEL_CAPACITY, ASYNC_CHUNK_SIZE};
+use crate::{Error, KeyCase, Redactor, Result, error::Segment};
+
+/// The largest integer a JavaScript number can hold exactly, `2^53 - 1`
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// How the serializer writes integers, for consumers such as JavaScript that can't hold every
+/// integer in a number
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerFormat {
+    /// Every integer as a number
+    #[default]
+    Number,
+    /// Integers outside `±(2^53 - 1)` as strings, and the rest as numbers
+    LargeAsString,
+    /// Integers of 64 bits or wider as strings, whatever their value
+    WideAsString,
+}
 
 pub struct Serializer<'a, W: Write> {
     output: &'a mut W,
+    /// Whether the innermost open container has had nothing written to it yet
     start: bool,
+    integers: IntegerFormat,
+    keys: KeyCase,
+    /// Whether map keys are renamed along with struct fields and variants
+    map_keys: bool,
+    redactor: Option<&'a Redactor>,
+    /// The key paths to write, if not everything
+    projection: Option<Vec<Vec<String>>>,
+    /// Whether the map value about to be written belongs to a key that was left out
+    skip_value: bool,
+    /// Where the value being written is, while that's needed
+    path: Vec<Segment>,
 }
 
 impl<'a, W: Write> Serializer<'a, W> {
@@ -19,7 +49,147 @@ impl<'a, W: Write> Serializer<'a, W> {
         Self {
             output,
             start: false,
+            integers: IntegerFormat::Number,
+            keys: KeyCase::Unchanged,
+            map_keys: false,
+            redactor: None,
+            projection: None,
+            skip_value: false,
+            path: Vec::new(),
+        }
+    }
+
+    /// Mask the values `redactor` matches
+    pub fn redact(mut self, redactor: &'a Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Only write the values at `paths`, such as `owner/email`, and what leads to them.
+    ///
+    /// Paths are object keys and struct fields separated by `/`. Array elements are written
+    /// whenever their array is, so `items/id` selects the `id` of every element of `items`.
+    pub fn project(mut self, paths: &[&str]) -> Self {
+        let paths = paths
+            .iter()
+            .map(|path| path.split('/').map(str::to_string).collect())
+            .collect();
+        self.projection = Some(paths);
+        self
+    }
+
+    /// Whether the path to the value being written is tracked
+    fn tracking(&self) -> bool {
+        self.redactor.is_some() || self.projection.is_some()
+    }
+
+    /// Whether the value under `key` in the current container is to be written
+    fn selected(&self, key: &str) -> bool {
+        let Some(projection) = &self.projection else {
+            return true;
+        };
+        let keys = self
+            .path
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Key(key) => Some(key.as_str()),
+                Segment::Index(_) => None,
+            })
+            .chain([key])
+            .collect::<Vec<_>>();
+        // Either the value leads to a selected path, or it's inside one
+        projection.iter().any(|path| {
+            path.iter()
+                .zip(&keys)
+                .all(|(selected, key)| selected == key)
+        })
+    }
+
+    fn enter(&mut self, segment: Segment) {
+        if self.tracking() {
+            self.path.push(segment);
+        }
+    }
+
+    fn leave(&mut self) {
+        if self.tracking() {
+            self.path.pop();
+        }
+    }
+
+    /// Write a value inside a container, masking it if the redactor matches its path
+    fn write_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        if let Some(redactor) = self.redactor
+            && redactor.matches(&self.path)
+        {
+            let mut json = Vec::new();
+            if redactor.hashes() {
+                value.serialize(&mut Serializer::new(&mut json))?;
+            }
+            return serde::Serializer::serialize_str(&mut *self, &redactor.replacement(&json));
+        }
+        value.serialize(&mut *self)
+    }
+
+    /// Rename struct fields and enum variants into `case`
+    ///
+    /// Serde writes the fields of `#[serde(flatten)]` structs as map keys, which are only renamed
+    /// with [`rename_map_keys`](Self::rename_map_keys), and the tag of an internally tagged enum
+    /// as a plain string, which isn't renamed. Use `#[serde(rename_all)]` on those types instead.
+    pub fn key_case(mut self, case: KeyCase) -> Self {
+        self.keys = case;
+        self
+    }
+
+    /// Also rename string map keys into the [`key_case`](Self::key_case)
+    pub fn rename_map_keys(mut self, rename: bool) -> Self {
+        self.map_keys = rename;
+        self
+    }
+
+    /// Write a struct field's name, renamed into the key case, unless the field is left out
+    ///
+    /// Returns whether the field's value should be written.
+    fn write_field(&mut self, key: &str) -> Result<bool> {
+        let key = self.keys.apply(key);
+        if !self.selected(&key) {
+            return Ok(false);
         }
+        if !self.start {
+            self.output.write_all(b",")?;
+        } else {
+            self.start = false;
+        }
+        serde::Serializer::serialize_str(&mut *self, &key)?;
+        self.output.write_all(b":")?;
+        self.enter(Segment::Key(key.into_owned()));
+        Ok(true)
+    }
+
+    /// Set how integers are written
+    pub fn integer_format(mut self, format: IntegerFormat) -> Self {
+        self.integers = format;
+        self
+    }
+
+    /// Write an integer, quoting it if the integer format calls for it
+    ///
+    /// `wide` is whether the integer's type has 64 bits or more, and `safe` is whether its value
+    /// is within `±(2^53 - 1)`.
+    fn write_integer(&mut self, digits: &str, wide: bool, safe: bool) -> Result<()> {
+        let quote = match self.integers {
+            IntegerFormat::Number => false,
+            IntegerFormat::LargeAsString => !safe,
+            IntegerFormat::WideAsString => wide,
+        };
+        if quote {
+            self.output.write_all(b"\"")?;
+        }
+        self.output.write_all(digits.as_bytes())?;
+        if quote {
+            self.output.write_all(b"\"")?;
+        }
+        Ok(())
     }
 }
 
@@ -41,33 +211,49 @@ impl<W: Write> serde::Serializer for &mut Serializer<'_, W> {
     }
 
     fn serialize_i8(self, v: i8) -> std::result::Result<Self::Ok, Self::Error> {
-        self.serialize_i64(v as i64)
+        self.write_integer(itoa::Buffer::new().format(v), false, true)
     }
     fn serialize_i16(self, v: i16) -> std::result::Result<Self::Ok, Self::Error> {
-        self.serialize_i64(v as i64)
+        self.write_integer(itoa::Buffer::new().format(v), false, true)
     }
     fn serialize_i32(self, v: i32) -> std::result::Result<Self::Ok, Self::Error> {
-        self.serialize_i64(v as i64)
+        self.write_integer(itoa::Buffer::new().format(v), false, true)
     }
     fn serialize_i64(self, v: i64) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output
-            .write_all(itoa::Buffer::new().format(v).as_bytes())?;
-        Ok(())
+        self.write_integer(
+            itoa::Buffer::new().format(v),
+            true,
+            v.unsigned_abs() <= MAX_SAFE_INTEGER,
+        )
+    }
+
+    fn serialize_i128(self, v: i128) -> std::result::Result<Self::Ok, Self::Error> {
+        self.write_integer(
+            itoa::Buffer::new().format(v),
+            true,
+            v.unsigned_abs() <= MAX_SAFE_INTEGER as u128,
+        )
     }
 
     fn serialize_u8(self, v: u8) -> std::result::Result<Self::Ok, Self::Error> {
-        self.serialize_u64(v as u64)
+        self.write_integer(itoa::Buffer::new().format(v), false, true)
     }
     fn serialize_u16(self, v: u16) -> std::result::Result<Self::Ok, Self::Error> {
-        self.serialize_u64(v as u64)
+        self.write_integer(itoa::Buffer::new().format(v), false, true)
     }
     fn serialize_u32(self, v: u32) -> std::result::Result<Self::Ok, Self::Error> {
-        self.serialize_u64(v as u64)
+        self.write_integer(itoa::Buffer::new().format(v), false, true)
     }
     fn serialize_u64(self, v: u64) -> std::result::Result<Self::Ok, Self::Error> {
-        self.output
-            .write_all(itoa::Buffer::new().format(v).as_bytes())?;
-        Ok(())
+        self.write_integer(itoa::Buffer::new().format(v), true, v <= MAX_SAFE_INTEGER)
+    }
+
+    fn serialize_u128(self, v: u128) -> std::result::Result<Self::Ok, Self::Error> {
+        self.write_integer(
+            itoa::Buffer::new().format(v),
+            true,
+            v <= MAX_SAFE_INTEGER as u128,
+        )
     }
 
     fn serialize_f32(self, v: f32) -> std::result::Result<Self::Ok, Self::Error> {
@@ -127,17 +313,27 @@ impl<W: Write> serde::Serializer for &mut Serializer<'_, W> {
         _variant_index: u32,
         variant: &'static str,
     ) -> std::result::Result<Self::Ok, Self::Error> {
-        self.serialize_str(variant)
+        let variant = self.keys.apply(variant);
+        self.serialize_str(&variant)
     }
 
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> std::result::Result<Self::Ok, Self::Error>
     where
         T: ?Sized + Serialize,
     {
+        #[cfg(feature = "arbitrary_precision")]
+        if name == crate::number::TOKEN {
+            // The value is the literal's text, which needs no escaping, so unquoting is enough
+            let mut text = Vec::new();
+            value.serialize(&mut Serializer::new(&mut text))?;
+            self.output.write_all(&text[1..text.len() - 1])?;
+            return Ok(());
+        }
+        let _ = name;
         value.serialize(self)
     }
 
@@ -151,10 +347,13 @@ impl<W: Write> serde::Serializer for &mut Serializer<'_, W> {
     where
         T: ?Sized + Serialize,
     {
+        let variant = self.keys.apply(variant);
         self.output.write_all(b"{")?;
         variant.serialize(&mut *self)?;
         self.output.write_all(b":")?;
-        value.serialize(&mut *self)?;
+        self.enter(Segment::Key(variant.into_owned()));
+        self.write_value(value)?;
+        self.leave();
         self.output.write_all(b"}")?;
         Ok(())
     }
@@ -165,6 +364,7 @@ impl<W: Write> serde::Serializer for &mut Serializer<'_, W> {
     ) -> std::result::Result<Self::SerializeSeq, Self::Error> {
         self.start = true;
         self.output.write_all(b"[")?;
+        self.enter(Segment::Index(0));
         Ok(self)
     }
 
@@ -187,9 +387,11 @@ impl<W: Write> serde::Serializer for &mut Serializer<'_, W> {
         variant: &'static str,
         len: usize,
     ) -> std::result::Result<Self::SerializeTupleVariant, Self::Error> {
+        let variant = self.keys.apply(variant);
         self.output.write_all(b"{")?;
         variant.serialize(&mut *self)?;
         self.output.write_all(b":")?;
+        self.enter(Segment::Key(variant.into_owned()));
         self.serialize_seq(Some(len))
     }
 
@@ -217,9 +419,11 @@ impl<W: Write> serde::Serializer for &mut Serializer<'_, W> {
         variant: &'static str,
         len: usize,
     ) -> std::result::Result<Self::SerializeStructVariant, Self::Error> {
+        let variant = self.keys.apply(variant);
         self.output.write_all(b"{")?;
         variant.serialize(&mut *self)?;
         self.output.write_all(b":")?;
+        self.enter(Segment::Key(variant.into_owned()));
         self.serialize_map(Some(len))
     }
 }
@@ -237,10 +441,17 @@ impl<W: Write> SerializeSeq for &mut Serializer<'_, W> {
         } else {
             self.start = false;
         }
-        value.serialize(&mut **self)
+        self.write_value(value)?;
+        if let Some(Segment::Index(index)) = self.path.last_mut() {
+            *index += 1;
+        }
+        Ok(())
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.leave();
+        // Back in the outer container, which this one was written into
+        self.start = false;
         self.output.write_all(b"]")?;
         Ok(())
     }
@@ -290,6 +501,10 @@ impl<W: Write> SerializeTupleVariant for &mut Serializer<'_, W> {
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        // The element index, then the variant
+        self.leave();
+        self.leave();
+        self.start = false;
         self.output.write_all(b"]}")?;
         Ok(())
     }
@@ -303,15 +518,37 @@ impl<W: Write> SerializeMap for &mut Serializer<'_, W> {
     where
         T: ?Sized + Serialize,
     {
+        // Buffered so the key can be read back for the path
+        let mut json = Vec::new();
+        key.serialize(&mut KeySerializer {
+            output: &mut json,
+            case: if self.map_keys {
+                self.keys
+            } else {
+                KeyCase::Unchanged
+            },
+        })?;
+        let key = if self.tracking() {
+            Some(crate::from_bytes::<String>(&json)?)
+        } else {
+            None
+        };
+        if let Some(key) = &key
+            && !self.selected(key)
+        {
+            self.skip_value = true;
+            return Ok(());
+        }
         if !self.start {
             self.output.write_all(b",")?;
         } else {
             self.start = false;
         }
-        key.serialize(&mut KeySerializer {
-            output: &mut *self.output,
-        })?;
+        self.output.write_all(&json)?;
         self.output.write_all(b":")?;
+        if let Some(key) = key {
+            self.path.push(Segment::Key(key));
+        }
         Ok(())
     }
 
@@ -319,10 +556,16 @@ impl<W: Write> SerializeMap for &mut Serializer<'_, W> {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        if std::mem::take(&mut self.skip_value) {
+            return Ok(());
+        }
+        self.write_value(value)?;
+        self.leave();
+        Ok(())
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.start = false;
         self.output.write_all(b"}")?;
         Ok(())
     }
@@ -340,7 +583,11 @@ impl<W: Write> SerializeStruct for &mut Serializer<'_, W> {
     where
         T: ?Sized + Serialize,
     {
-        self.serialize_entry(key, value)
+        if self.write_field(key)? {
+            self.write_value(value)?;
+            self.leave();
+        }
+        Ok(())
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
@@ -360,10 +607,16 @@ impl<W: Write> SerializeStructVariant for &mut Serializer<'_, W> {
     where
         T: ?Sized + Serialize,
     {
-        self.serialize_entry(key, value)
+        if self.write_field(key)? {
+            self.write_value(value)?;
+            self.leave();
+        }
+        Ok(())
     }
 
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
+        self.leave();
+        self.start = false;
         self.output.write_all(b"}}")?;
         Ok(())
     }
@@ -371,6 +624,8 @@ impl<W: Write> SerializeStructVariant for &mut Serializer<'_, W> {
 
 struct KeySerializer<'a, W: Write> {
     output: &'a mut W,
+    /// The case to rename string keys into
+    case: KeyCase,
 }
 
 impl<W: Write> serde::Serializer for &mut KeySerializer<'_, W> {
@@ -385,33 +640,59 @@ impl<W: Write> serde::Serializer for &mut KeySerializer<'_, W> {
     type SerializeStruct = Impossible<(), Error>;
     type SerializeStructVariant = Impossible<(), Error>;
 
-    fn serialize_bool(self, _v: bool) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_bool(self, v: bool) -> std::result::Result<Self::Ok, Self::Error> {
+        self.output
+            .write_all(if v { b"\"true\"" } else { b"\"false\"" })?;
+        Ok(())
     }
-    fn serialize_i8(self, _v: i8) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i8(self, v: i8) -> std::result::Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
     }
-    fn serialize_i16(self, _v: i16) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i16(self, v: i16) -> std::result::Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
     }
-    fn serialize_i32(self, _v: i32) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i32(self, v: i32) -> std::result::Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v as i64)
     }
-    fn serialize_i64(self, _v: i64) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i64(self, v: i64) -> std::result::Result<Self::Ok, Self::Error> {
+        self.output.write_all(b"\"")?;
+        self.output
+            .write_all(itoa::Buffer::new().format(v).as_bytes())?;
+        self.output.write_all(b"\"")?;
+        Ok(())
     }
 
-    fn serialize_u8(self, _v: u8) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_i128(self, v: i128) -> std::result::Result<Self::Ok, Self::Error> {
+        self.output.write_all(b"\"")?;
+        self.output
+            .write_all(itoa::Buffer::new().format(v).as_bytes())?;
+        self.output.write_all(b"\"")?;
+        Ok(())
     }
-    fn serialize_u16(self, _v: u16) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+
+    fn serialize_u8(self, v: u8) -> std::result::Result<Self::Ok, Self::Error> {
+        self.serialize_u64(v as u64)
     }
-    fn serialize_u32(self, _v: u32) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_u16(self, v: u16) -> std::result::Result<Self::Ok, Self::Error> {
+        self.serialize_u64(v as u64)
     }
-    fn serialize_u64(self, _v: u64) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+    fn serialize_u32(self, v: u32) -> std::result::Result<Self::Ok, Self::Error> {
+        self.serialize_u64(v as u64)
+    }
+    fn serialize_u64(self, v: u64) -> std::result::Result<Self::Ok, Self::Error> {
+        self.output.write_all(b"\"")?;
+        self.output
+            .write_all(itoa::Buffer::new().format(v).as_bytes())?;
+        self.output.write_all(b"\"")?;
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> std::result::Result<Self::Ok, Self::Error> {
+        self.output.write_all(b"\"")?;
+        self.output
+            .write_all(itoa::Buffer::new().format(v).as_bytes())?;
+        self.output.write_all(b"\"")?;
+        Ok(())
     }
 
     fn serialize_f32(self, _v: f32) -> std::result::Result<Self::Ok, Self::Error> {
@@ -426,11 +707,7 @@ impl<W: Write> serde::Serializer for &mut KeySerializer<'_, W> {
     }
 
     fn serialize_str(self, v: &str) -> std::result::Result<Self::Ok, Self::Error> {
-        Serializer {
-            start: false,
-            output: &mut *self.output,
-        }
-        .serialize_str(v)
+        Serializer::new(&mut *self.output).serialize_str(&self.case.apply(v))
     }
 
     fn serialize_bytes(self, _v: &[u8]) -> std::result::Result<Self::Ok, Self::Error> {
@@ -463,9 +740,9 @@ impl<W: Write> serde::Serializer for &mut KeySerializer<'_, W> {
         self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> std::result::Result<Self::Ok, Self::Error> {
-        Err(Error::KeyNotString)
+        self.serialize_str(variant)
     }
 
     fn serialize_newtype_struct<T>(
@@ -558,6 +835,15 @@ pub fn to_string(value: &impl Serialize) -> Result<String> {
     Ok(unsafe { String::from_utf8_unchecked(out) })
 }
 
+/// Serialize only the parts of `value` at `paths`, as described for [`Serializer::project`]
+pub fn to_string_projected(value: &impl Serialize, paths: &[&str]) -> Result<String> {
+    let mut out = Vec::new();
+    let mut serializer = Serializer::new(&mut out).project(paths);
+    value.serialize(&mut serializer)?;
+    // SAFETY: The serializer implementation only ever writes valid UTF-8.
+    Ok(unsafe { String::from_utf8_unchecked(out) })
+}
+
 pub fn to_bytes(value: &impl Serialize) -> Result<Vec<u8>> {
     let mut out = Vec::new();
     let mut serializer = Serializer::new(&mut out);
@@ -571,6 +857,83 @@ pub fn to_writer(value: &impl Serialize, writer: &mut impl Write) -> Result<()>
     Ok(())
 }
 
+/// Serialize `value` into an async writer.
+///
+/// The value is serialized on tokio's blocking thread pool into chunks of bounded size, and each
+/// chunk is written out before the serializer is allowed to get too far ahead, so the output is
+/// never materialized in full.
+///
+/// The blocking task may outlive a cancelled call, so it can't borrow the value; the [`Arc`]
+/// shares it instead of copying it. Pass `&mut writer` to keep using the writer afterwards.
+///
+/// [`Arc`]: std::sync::Arc
+#[cfg(feature = "async")]
+pub async fn to_async_writer<T>(
+    value: std::sync::Arc<T>,
+    mut writer: impl tokio::io::AsyncWrite + Unpin,
+) -> Result<()>
+where
+    T: Serialize + Send + Sync + ?Sized + 'static,
+{
+    use tokio::io::AsyncWriteExt;
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(ASYNC_CHANNEL_CAPACITY);
+    let serialize = tokio::task::spawn_blocking(move || {
+        let mut out = ChannelWriter {
+            tx,
+            buf: Vec::with_capacity(ASYNC_CHUNK_SIZE),
+        };
+        to_writer(&&*value, &mut out)?;
+        out.flush()?;
+        Result::Ok(())
+    });
+
+    let mut written: Result<()> = Ok(());
+    while let Some(chunk) = rx.recv().await {
+        if let Err(e) = writer.write_all(&chunk).await {
+            written = Err(e.into());
+            break;
+        }
+    }
+    // Dropping the receiver stops the serializer early if a write failed
+    drop(rx);
+    let serialized = serialize
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+    written?;
+    serialized?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// A blocking writer that sends its output to an async task in chunks
+#[cfg(feature = "async")]
+struct ChannelWriter {
+    tx: tokio::sync::mpsc::Sender<Vec<u8>>,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "async")]
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        if self.buf.len() >= ASYNC_CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(ASYNC_CHUNK_SIZE));
+        self.tx
+            .blocking_send(chunk)
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+    }
+}
+
 pub fn escape(c: char) -> String {
     match c {
         '"' => "\\\"".to_string(),
@@ -0,0 +1,606 @@
+use std::io::{BufRead, BufReader, Read};
+
+use crate::{Error, Result};
+
+/// The flavour of JSON accepted by a [`Tokenizer`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// Strict JSON
+    #[default]
+    Json,
+    /// JSON with comments: strict JSON, plus `//` and `/* */` comments and trailing commas
+    Jsonc,
+    /// [JSON5](https://spec.json5.org/): comments, trailing commas, unquoted keys,
+    /// single-quoted and multi-line strings, and the extended number forms
+    Json5,
+}
+
+impl Syntax {
+    fn comments(self) -> bool {
+        matches!(self, Self::Jsonc | Self::Json5)
+    }
+
+    fn trailing_commas(self) -> bool {
+        matches!(self, Self::Jsonc | Self::Json5)
+    }
+}
+
+/// A single token of a JSON document
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// An object key
+    Key(String),
+    String(String),
+    /// The text of a number literal. JSON5 hexadecimal literals are given in decimal.
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StartObject => write!(f, "{{"),
+            Self::EndObject => write!(f, "}}"),
+            Self::StartArray => write!(f, "["),
+            Self::EndArray => write!(f, "]"),
+            Self::Key(s) | Self::String(s) => write!(f, "{s:?}"),
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Null => write!(f, "null"),
+        }
+    }
+}
+
+/// A location in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// 0-based byte offset
+    pub offset: usize,
+}
+
+impl Position {
+    fn start() -> Self {
+        Self {
+            line: 1,
+            column: 1,
+            offset: 0,
+        }
+    }
+
+    fn advance(&mut self, byte: u8) {
+        self.offset += 1;
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if byte & 0xC0 != 0x80 {
+            // Only count the first byte of each UTF-8 sequence
+            self.column += 1;
+        }
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// A token and where it starts in the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub token: Token,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// What the tokenizer expects to read next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Value,
+    /// A value or `]`
+    FirstElement,
+    /// A key or `}`
+    FirstKey,
+    Key,
+    /// A `:` followed by a value
+    AfterKey,
+    /// A `,` or closing delimiter, or nothing after the top-level value
+    AfterValue,
+    Done,
+}
+
+/// A pull parser producing the [`Token`]s of a single JSON document.
+///
+/// The tokenizer checks that the document is well-formed as it goes, and stops after the
+/// top-level value without reading any further.
+pub struct Tokenizer<R: Read> {
+    input: BufReader<R>,
+    syntax: Syntax,
+    position: Position,
+    stack: Vec<Container>,
+    state: State,
+    peeked: Option<Event>,
+}
+
+impl<R: Read> Tokenizer<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input: BufReader::new(input),
+            syntax: Syntax::Json,
+            position: Position::start(),
+            stack: Vec::new(),
+            state: State::Value,
+            peeked: None,
+        }
+    }
+
+    /// Set the flavour of JSON to accept
+    pub fn syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// The position just past the last token read or peeked
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Read the next token, or `None` once the document is complete
+    pub fn next_event(&mut self) -> Result<Option<Event>> {
+        if let Some(event) = self.peeked.take() {
+            return Ok(Some(event));
+        }
+        self.read_located()
+    }
+
+    /// Look at the next token without consuming it
+    pub fn peek_event(&mut self) -> Result<Option<&Event>> {
+        if self.peeked.is_none() {
+            self.peeked = self.read_located()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    /// Read the next token, pointing any error at where it happened and stopping there
+    fn read_located(&mut self) -> Result<Option<Event>> {
+        let event = self.read_event();
+        if event.is_err() {
+            self.state = State::Done;
+        }
+        event.map_err(|e| e.at_position(self.position))
+    }
+
+    fn read_event(&mut self) -> Result<Option<Event>> {
+        match self.state {
+            State::Done => return Ok(None),
+            State::AfterValue => {
+                let Some(&container) = self.stack.last() else {
+                    self.state = State::Done;
+                    return Ok(None);
+                };
+                let close = match container {
+                    Container::Object => b'}',
+                    Container::Array => b']',
+                };
+                if self.peek_significant()? == close {
+                    return self.close();
+                }
+                self.expect_byte(b',')?;
+                if self.peek_significant()? == close {
+                    if self.syntax.trailing_commas() {
+                        return self.close();
+                    }
+                    return Err(Error::TrailingComma(close as char));
+                }
+                self.state = match container {
+                    Container::Object => State::Key,
+                    Container::Array => State::Value,
+                };
+            }
+            State::AfterKey => {
+                self.expect_byte(b':')?;
+                self.state = State::Value;
+            }
+            State::FirstElement => {
+                if self.peek_significant()? == b']' {
+                    return self.close();
+                }
+                self.state = State::Value;
+            }
+            State::FirstKey => {
+                if self.peek_significant()? == b'}' {
+                    return self.close();
+                }
+                self.state = State::Key;
+            }
+            State::Key | State::Value => {}
+        }
+
+        let first = self.peek_significant()?;
+        let position = self.position;
+        let token = if self.state == State::Key {
+            self.state = State::AfterKey;
+            Token::Key(self.read_key(first)?)
+        } else {
+            self.read_value(first)?
+        };
+        Ok(Some(Event { token, position }))
+    }
+
+    /// Close the innermost container, whose delimiter is the next byte
+    fn close(&mut self) -> Result<Option<Event>> {
+        let position = self.position;
+        let token = match self.stack.pop() {
+            Some(Container::Object) => Token::EndObject,
+            _ => Token::EndArray,
+        };
+        self.bump()?;
+        self.state = State::AfterValue;
+        Ok(Some(Event { token, position }))
+    }
+
+    fn read_key(&mut self, first: u8) -> Result<String> {
+        let json5 = self.syntax == Syntax::Json5;
+        match first {
+            b'"' => self.read_string(),
+            b'\'' if json5 => self.read_string(),
+            _ if json5 => self.read_identifier(),
+            c => Err(Error::Unexpected {
+                found: (c as char).to_string(),
+                expected: Some("\"".to_string()),
+            }),
+        }
+    }
+
+    fn read_value(&mut self, first: u8) -> Result<Token> {
+        let json5 = self.syntax == Syntax::Json5;
+        let token = match first {
+            b'{' => {
+                self.bump()?;
+                self.stack.push(Container::Object);
+                self.state = State::FirstKey;
+                return Ok(Token::StartObject);
+            }
+            b'[' => {
+                self.bump()?;
+                self.stack.push(Container::Array);
+                self.state = State::FirstElement;
+                return Ok(Token::StartArray);
+            }
+            b'"' => Token::String(self.read_string()?),
+            b'\'' if json5 => Token::String(self.read_string()?),
+            b't' => {
+                self.read_word(Vec::new(), "true")?;
+                Token::Bool(true)
+            }
+            b'f' => {
+                self.read_word(Vec::new(), "false")?;
+                Token::Bool(false)
+            }
+            b'n' => {
+                self.read_word(Vec::new(), "null")?;
+                Token::Null
+            }
+            b'-' | b'0'..=b'9' => Token::Number(self.read_number()?),
+            b'+' | b'.' | b'I' | b'N' if json5 => Token::Number(self.read_number()?),
+            c => {
+                return Err(Error::Unexpected {
+                    found: (c as char).to_string(),
+                    expected: None,
+                });
+            }
+        };
+        self.state = State::AfterValue;
+        Ok(token)
+    }
+
+    /// Look at the next byte without consuming it, or `None` at the end of input
+    fn peek_byte(&mut self) -> Result<Option<u8>> {
+        Ok(self.input.fill_buf()?.first().copied())
+    }
+
+    /// Consume the next byte
+    fn bump(&mut self) -> Result<u8> {
+        let next = self.peek_byte()?.ok_or(Error::Eof)?;
+        self.input.consume(1);
+        self.position.advance(next);
+        Ok(next)
+    }
+
+    /// Skip whitespace and comments, returning the next byte without consuming it
+    fn peek_significant(&mut self) -> Result<u8> {
+        loop {
+            match self.peek_byte()? {
+                None => return Err(Error::Eof),
+                Some(b'/') if self.syntax.comments() => {
+                    self.bump()?;
+                    self.skip_comment()?;
+                }
+                Some(b) if (b as char).is_whitespace() => {
+                    self.bump()?;
+                }
+                Some(b) => return Ok(b),
+            }
+        }
+    }
+
+    /// Skip the rest of a comment whose leading `/` has already been read
+    fn skip_comment(&mut self) -> Result<()> {
+        match self.bump()? {
+            b'/' => while self.bump()? != b'\n' {},
+            b'*' => {
+                let mut star = false;
+                loop {
+                    let b = self.bump()?;
+                    if star && b == b'/' {
+                        break;
+                    }
+                    star = b == b'*';
+                }
+            }
+            c => {
+                return Err(Error::Unexpected {
+                    found: (c as char).to_string(),
+                    expected: Some("/ or *".to_string()),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn expect_byte(&mut self, expected: u8) -> Result<()> {
+        let next = self.peek_significant()?;
+        if next != expected {
+            return Err(Error::Unexpected {
+                found: (next as char).to_string(),
+                expected: Some((expected as char).to_string()),
+            });
+        }
+        self.bump()?;
+        Ok(())
+    }
+
+    /// Collect consecutive bytes matching `pred` onto `buf`
+    fn collect_while(&mut self, buf: &mut Vec<u8>, pred: impl Fn(u8) -> bool) -> Result<()> {
+        while let Some(peek) = self.peek_byte()? {
+            if !pred(peek) {
+                break;
+            }
+            buf.push(self.bump()?);
+        }
+        Ok(())
+    }
+
+    /// Read the bytes of `word` onto `buf`, erroring if they don't match
+    fn read_word(&mut self, mut buf: Vec<u8>, word: &str) -> Result<String> {
+        let start = buf.len();
+        for _ in 0..word.len() {
+            buf.push(self.bump()?);
+        }
+        if &buf[start..] != word.as_bytes() {
+            return Err(Error::Unexpected {
+                found: String::from_utf8_lossy(&buf[start..]).into_owned(),
+                expected: Some(word.to_string()),
+            });
+        }
+        Ok(String::from_utf8(buf)?)
+    }
+
+    /// Read the text of a number literal
+    fn read_number(&mut self) -> Result<String> {
+        let json5 = self.syntax == Syntax::Json5;
+        let mut buf = Vec::new();
+        if let Some(b'-') = self.peek_byte()? {
+            buf.push(self.bump()?);
+        } else if let (true, Some(b'+')) = (json5, self.peek_byte()?) {
+            buf.push(self.bump()?);
+        }
+        if json5 {
+            match self.peek_byte()? {
+                Some(b'I') => return self.read_word(buf, "Infinity"),
+                Some(b'N') => return self.read_word(buf, "NaN"),
+                Some(b'0') => {
+                    buf.push(self.bump()?);
+                    if let Some(b'x' | b'X') = self.peek_byte()? {
+                        self.bump()?;
+                        let mut digits = Vec::new();
+                        self.collect_while(&mut digits, |b| b.is_ascii_hexdigit())?;
+                        if digits.is_empty() {
+                            return Err(Error::Unexpected {
+                                found: String::from_utf8(buf)? + "x",
+                                expected: Some("hexadecimal digits".to_string()),
+                            });
+                        }
+                        let magnitude = u128::from_str_radix(&String::from_utf8(digits)?, 16)?;
+                        let sign = if buf[0] == b'-' { "-" } else { "" };
+                        return Ok(format!("{sign}{magnitude}"));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.collect_while(&mut buf, |b| b.is_ascii_digit())?;
+        if self.peek_byte()? == Some(b'.') {
+            buf.push(self.bump()?);
+            self.collect_while(&mut buf, |b| b.is_ascii_digit())?;
+        }
+        if let Some(b'e' | b'E') = self.peek_byte()? {
+            buf.push(self.bump()?);
+            if let Some(b'+' | b'-') = self.peek_byte()? {
+                buf.push(self.bump()?);
+            }
+            self.collect_while(&mut buf, |b| b.is_ascii_digit())?;
+        }
+        let text = String::from_utf8(buf)?;
+        if !is_valid_number(text.trim_start_matches(['-', '+']), json5) {
+            return Err(Error::Unexpected {
+                found: text,
+                expected: Some("number".to_string()),
+            });
+        }
+        Ok(text)
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let quote = self.bump()?;
+        let mut buf = Vec::new();
+        loop {
+            let start = buf.len();
+            self.input.read_until(quote, &mut buf)?;
+            for &b in &buf[start..] {
+                self.position.advance(b);
+            }
+            if buf.last() != Some(&quote) {
+                return Err(Error::Eof);
+            }
+            // The quote is escaped if it follows an odd number of backslashes
+            let backslashes = buf[..buf.len() - 1]
+                .iter()
+                .rev()
+                .take_while(|b| **b == b'\\')
+                .count();
+            if backslashes % 2 == 0 {
+                break;
+            }
+        }
+        buf.pop();
+        unescape(&String::from_utf8(buf)?, self.syntax)
+    }
+
+    /// Read a JSON5 unquoted object key
+    fn read_identifier(&mut self) -> Result<String> {
+        let first = self.bump()?;
+        if !is_identifier_start(first) {
+            return Err(Error::Unexpected {
+                found: (first as char).to_string(),
+                expected: Some("object key".to_string()),
+            });
+        }
+        let mut buf = vec![first];
+        self.collect_while(&mut buf, |b| is_identifier_start(b) || b.is_ascii_digit())?;
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+impl<R: Read> Iterator for Tokenizer<R> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+/// Check whether `text` is a JSON number literal, such as `-1.5e3`
+pub(crate) fn is_number_literal(text: &str) -> bool {
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    // Signs may only follow the exponent marker
+    let numeric = unsigned.bytes().enumerate().all(|(i, b)| match b {
+        b'+' | b'-' => i > 0 && matches!(unsigned.as_bytes()[i - 1], b'e' | b'E'),
+        _ => b.is_ascii_digit() || b"eE.".contains(&b),
+    });
+    numeric && is_valid_number(unsigned, false)
+}
+
+/// Check the shape of an unsigned decimal number literal whose bytes are already known to be valid
+fn is_valid_number(text: &str, json5: bool) -> bool {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(i) => (&text[..i], Some(&text[i + 1..])),
+        None => (text, None),
+    };
+    let (int, frac) = match mantissa.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (mantissa, None),
+    };
+    let digits = if json5 {
+        // Either side of the decimal point may be empty, but not both
+        !int.is_empty() || frac.is_some_and(|frac| !frac.is_empty())
+    } else {
+        !int.is_empty() && frac.is_none_or(|frac| !frac.is_empty())
+    };
+    let leading_zero = int.len() > 1 && int.starts_with('0');
+    let exponent = exponent.is_none_or(|e| !e.trim_start_matches(['+', '-']).is_empty());
+    digits && !leading_zero && exponent
+}
+
+fn is_identifier_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'$' || !b.is_ascii()
+}
+
+/// Read the four hex digits of a `\u` escape
+fn read_hex4(chars: &mut std::str::Chars) -> Result<u32> {
+    let hex = chars.as_str().get(..4).ok_or(Error::InvalidEscape)?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::InvalidEscape);
+    }
+    chars.nth(3);
+    u32::from_str_radix(hex, 16).map_err(|_| Error::InvalidEscape)
+}
+
+fn unescape(s: &str, syntax: Syntax) -> Result<String> {
+    let json5 = syntax == Syntax::Json5;
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let c = chars.next().unwrap();
+            match c {
+                'v' if json5 => out.push('\x0b'),
+                '0' if json5 => out.push('\0'),
+                'x' if json5 => {
+                    let hex = chars.by_ref().take(2).collect::<String>();
+                    let byte = u8::from_str_radix(&hex, 16).map_err(|_| Error::InvalidEscape)?;
+                    out.push(char::from(byte));
+                }
+                // Line continuations
+                '\n' | '\u{2028}' | '\u{2029}' if json5 => {}
+                '\r' if json5 => {
+                    if chars.as_str().starts_with('\n') {
+                        chars.next();
+                    }
+                }
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                'b' => out.push('\x08'),
+                'f' => out.push('\x0c'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'u' => {
+                    let mut code = read_hex4(&mut chars)?;
+                    // Characters outside the BMP are escaped as a high then a low surrogate
+                    if (0xD800..0xDC00).contains(&code) {
+                        if !chars.as_str().starts_with("\\u") {
+                            return Err(Error::InvalidEscape);
+                        }
+                        chars.nth(1);
+                        let low = read_hex4(&mut chars)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return Err(Error::InvalidEscape);
+                        }
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    // Lone low surrogates aren't characters either
+                    out.push(char::from_u32(code).ok_or(Error::InvalidEscape)?);
+                }
+                _ => out.push(c),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,178 @@
+use std::io::Write;
+
+use serde::{Serialize, Serializer as _};
+
+use crate::{Error, Result, Serializer};
+
+#[derive(Debug, Clone, Copy)]
+enum Container {
+    Object,
+    Array,
+}
+
+struct Frame {
+    container: Container,
+    /// Whether nothing has been written into the container yet
+    first: bool,
+    /// Whether a key has been written without its value
+    keyed: bool,
+}
+
+/// A push-style writer for documents that aren't available as a single [`Serialize`] value.
+///
+/// Containers are opened with [`begin_object`](Self::begin_object) and
+/// [`begin_array`](Self::begin_array), filled with [`key`](Self::key) and
+/// [`value`](Self::value), and closed with [`end`](Self::end). Calls that would produce malformed
+/// JSON return an error instead of writing anything.
+pub struct JsonWriter<W: Write> {
+    output: W,
+    stack: Vec<Frame>,
+    done: bool,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(output: W) -> Self {
+        Self {
+            output,
+            stack: Vec::new(),
+            done: false,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<()> {
+        self.begin(Container::Object)
+    }
+
+    pub fn begin_array(&mut self) -> Result<()> {
+        self.begin(Container::Array)
+    }
+
+    /// Write an object key, which must be followed by a value
+    pub fn key(&mut self, key: &str) -> Result<()> {
+        match self.stack.last_mut() {
+            Some(
+                frame @ Frame {
+                    container: Container::Object,
+                    keyed: false,
+                    ..
+                },
+            ) => {
+                if !frame.first {
+                    self.output.write_all(b",")?;
+                }
+                frame.first = false;
+                frame.keyed = true;
+            }
+            _ => return Err(self.misplaced("key")),
+        }
+        Serializer::new(&mut self.output).serialize_str(key)?;
+        self.output.write_all(b":")?;
+        Ok(())
+    }
+
+    /// Write a complete value
+    pub fn value(&mut self, value: &impl Serialize) -> Result<()> {
+        // Buffered so a value that fails to serialize leaves the output untouched
+        let mut json = Vec::new();
+        value.serialize(&mut Serializer::new(&mut json))?;
+        self.before_value("value")?;
+        self.output.write_all(&json)?;
+        self.after_value();
+        Ok(())
+    }
+
+    /// Close the innermost object or array
+    pub fn end(&mut self) -> Result<()> {
+        let close = match self.stack.last() {
+            Some(Frame { keyed: true, .. }) | None => return Err(self.misplaced("end")),
+            Some(Frame {
+                container: Container::Object,
+                ..
+            }) => b"}",
+            Some(Frame {
+                container: Container::Array,
+                ..
+            }) => b"]",
+        };
+        self.output.write_all(close)?;
+        self.stack.pop();
+        self.after_value();
+        Ok(())
+    }
+
+    /// Check that the document is complete and return the output
+    pub fn finish(self) -> Result<W> {
+        match self.stack.last() {
+            Some(Frame {
+                container: Container::Object,
+                ..
+            }) => Err(Error::Unclosed('{')),
+            Some(Frame {
+                container: Container::Array,
+                ..
+            }) => Err(Error::Unclosed('[')),
+            None if !self.done => Err(self.misplaced("end of document")),
+            None => Ok(self.output),
+        }
+    }
+
+    fn begin(&mut self, container: Container) -> Result<()> {
+        let (found, open) = match container {
+            Container::Object => ("object", b"{"),
+            Container::Array => ("array", b"["),
+        };
+        self.before_value(found)?;
+        self.output.write_all(open)?;
+        self.stack.push(Frame {
+            container,
+            first: true,
+            keyed: false,
+        });
+        Ok(())
+    }
+
+    /// Write the separator before a value, erroring if one isn't allowed
+    fn before_value(&mut self, found: &'static str) -> Result<()> {
+        match self.stack.last_mut() {
+            None if !self.done => {}
+            Some(
+                frame @ Frame {
+                    container: Container::Array,
+                    ..
+                },
+            ) => {
+                if !frame.first {
+                    self.output.write_all(b",")?;
+                }
+                frame.first = false;
+            }
+            Some(frame @ Frame { keyed: true, .. }) => frame.keyed = false,
+            _ => return Err(self.misplaced(found)),
+        }
+        Ok(())
+    }
+
+    fn after_value(&mut self) {
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+    }
+
+    /// The error for writing `found` where something else was expected
+    fn misplaced(&self, found: &'static str) -> Error {
+        let expected = match self.stack.last() {
+            None if self.done => "end of document",
+            None => "value",
+            Some(Frame { keyed: true, .. }) => "value",
+            Some(Frame {
+                container: Container::Object,
+                ..
+            }) => "key or end",
+            Some(Frame {
+                container: Container::Array,
+                ..
+            }) => "value or end",
+        };
+        Error::Misplaced { found, expected }
+    }
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "async")]
+
+use serde::{Deserialize, Serialize};
+use serde_json_exercise as json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[tokio::test]
+async fn from_async_reader() {
+    // A small buffer forces the document to arrive over several reads
+    let (mut client, server) = tokio::io::duplex(4);
+    let write = async move {
+        for part in [r#"[{"x":1,"#, r#""y":2},"#, r#"{"x":3,"y":4}]"#] {
+            client.write_all(part.as_bytes()).await.unwrap();
+        }
+        // Keep the stream open: parsing should finish on its own
+        client
+    };
+    let (json, _client) = tokio::join!(json::from_async_reader::<Vec<Point>>(server), write);
+    assert_eq!(
+        json.expect("Failed to deserialize"),
+        [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }]
+    );
+}
+
+#[tokio::test]
+async fn from_async_reader_truncated() {
+    let (mut client, server) = tokio::io::duplex(64);
+    client.write_all(br#"{"x":1"#).await.unwrap();
+    drop(client);
+    let error = json::from_async_reader::<Point>(server).await.unwrap_err();
+    assert!(error.is_eof());
+}
+
+#[tokio::test]
+async fn to_async_writer() {
+    let points = (0..10_000)
+        .map(|i| Point { x: i, y: -i })
+        .collect::<Vec<_>>();
+    let expected = json::to_string(&points).expect("Failed to serialize");
+
+    // The pipe is far smaller than the output, so writing has to interleave with reading
+    let (mut client, mut server) = tokio::io::duplex(1024);
+    // Shared with the serializer rather than copied
+    let points = std::sync::Arc::new(points);
+    let write = async move {
+        json::to_async_writer(points, &mut client).await?;
+        drop(client);
+        json::Result::Ok(())
+    };
+    let mut out = String::new();
+    let (written, read) = tokio::join!(write, server.read_to_string(&mut out));
+    written.expect("Failed to serialize");
+    read.unwrap();
+    assert_eq!(out, expected);
+}
@@ -33,6 +33,28 @@ fn escape_ascii_control() {
     assert_eq!(json, "\x0F");
 }
 
+#[test]
+fn escape_unicode() {
+    let input = r#""\u4e2d\u00E9\ud83d\ude00""#;
+    let json = json::from_str::<String>(input).expect("Failed to deserialize");
+    assert_eq!(json, "中é😀");
+
+    for input in [
+        r#""\ud83d""#,
+        r#""\ud83dx""#,
+        r#""\ud83d\u0041""#,
+        r#""\ude00""#,
+        r#""\u12""#,
+        r#""\u+123""#,
+    ] {
+        let error = json::from_str::<String>(input).unwrap_err();
+        assert!(
+            matches!(error.inner(), json::Error::InvalidEscape),
+            "{input} should not unescape"
+        );
+    }
+}
+
 #[test]
 fn unclosed_string() {
     let input = r#""droddyrox"#;
@@ -55,6 +77,323 @@ fn map() {
     assert_eq!(json.get("b"), Some(&"rox".to_string()));
 }
 
+#[test]
+fn non_string_keys() {
+    let input = r#"{"-1":"droddy","2":"rox"}"#;
+    let json = json::from_str::<std::collections::BTreeMap<i32, String>>(input)
+        .expect("Failed to deserialize");
+    assert_eq!(json[&-1], "droddy");
+    assert_eq!(json[&2], "rox");
+
+    let input = r#"{"true":1,"false":0}"#;
+    let json = json::from_str::<std::collections::HashMap<bool, u8>>(input)
+        .expect("Failed to deserialize");
+    assert_eq!(json[&true], 1);
+
+    #[derive(Debug, PartialEq, Eq, Hash, Deserialize)]
+    enum Color {
+        Red,
+    }
+    let input = r#"{"Red":1}"#;
+    let json = json::from_str::<std::collections::HashMap<Color, u8>>(input)
+        .expect("Failed to deserialize");
+    assert_eq!(json[&Color::Red], 1);
+
+    let input = r#"{"droddy":1}"#;
+    let error = json::from_str::<std::collections::HashMap<u8, u8>>(input).unwrap_err();
+    assert!(error.is_data());
+}
+
+#[test]
+fn int128() {
+    let input = "[-170141183460469231731687303715884105728, 18446744073709551616]";
+    let json = json::from_str::<(i128, u128)>(input).expect("Failed to deserialize");
+    assert_eq!(json, (i128::MIN, u64::MAX as u128 + 1));
+
+    let input = r#"{"340282366920938463463374607431768211455":1}"#;
+    let json = json::from_str::<std::collections::HashMap<u128, u8>>(input)
+        .expect("Failed to deserialize");
+    assert_eq!(json[&u128::MAX], 1);
+
+    /// Records which visit method `deserialize_any` picked
+    #[derive(Debug, PartialEq)]
+    enum Amount {
+        Unsigned(u64),
+        Signed(i64),
+        Large(u128),
+        Negative(i128),
+    }
+
+    impl<'de> Deserialize<'de> for Amount {
+        fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+            struct Visitor;
+
+            impl serde::de::Visitor<'_> for Visitor {
+                type Value = Amount;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("an integer")
+                }
+
+                fn visit_u64<E>(self, v: u64) -> Result<Amount, E> {
+                    Ok(Amount::Unsigned(v))
+                }
+
+                fn visit_i64<E>(self, v: i64) -> Result<Amount, E> {
+                    Ok(Amount::Signed(v))
+                }
+
+                fn visit_u128<E>(self, v: u128) -> Result<Amount, E> {
+                    Ok(Amount::Large(v))
+                }
+
+                fn visit_i128<E>(self, v: i128) -> Result<Amount, E> {
+                    Ok(Amount::Negative(v))
+                }
+            }
+
+            de.deserialize_any(Visitor)
+        }
+    }
+
+    let input = "[1, -1, 18446744073709551616, -9223372036854775809]";
+    let json = json::from_str::<Vec<Amount>>(input).expect("Failed to deserialize");
+    assert_eq!(
+        json,
+        [
+            Amount::Unsigned(1),
+            Amount::Signed(-1),
+            Amount::Large(18446744073709551616),
+            Amount::Negative(-9223372036854775809)
+        ]
+    );
+}
+
+#[test]
+fn number_range() {
+    let error = json::from_str::<u8>("300").unwrap_err();
+    assert!(error.is_data());
+    assert!(matches!(
+        error.inner(),
+        json::Error::NumberOutOfRange { number, ty: "u8" } if number == "300"
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Number 300 is out of range for u8 (line 1, column 1)"
+    );
+
+    let error = json::from_str::<Vec<i64>>("[1, -9223372036854775809]").unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::NumberOutOfRange { ty: "i64", .. }
+    ));
+    assert_eq!(error.position().map(|p| p.column), Some(5));
+
+    assert_eq!(json::from_str::<u32>("-0").ok(), Some(0));
+    assert_eq!(json::from_str::<i8>("-0").ok(), Some(0));
+
+    let error = json::from_str::<u8>("1.5").unwrap_err();
+    assert!(matches!(error.inner(), json::Error::InvalidType { .. }));
+
+    // Integers too large for 128 bits fall back to an f64 by default
+    let input = "1000000000000000000000000000000000000000";
+    let json = json::from_str::<serde::de::IgnoredAny>(input);
+    assert!(json.is_ok());
+
+    let mut de = json::Deserializer::new(input.as_bytes()).strict_numbers(true);
+    let error = serde::de::IgnoredAny::deserialize(&mut de).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::NumberOutOfRange { ty: "u128", .. }
+    ));
+
+    // Errors from a deserializer used directly have positions too
+    let error = u8::deserialize(&mut json::Deserializer::new("300".as_bytes())).unwrap_err();
+    assert_eq!(error.position().map(|p| p.column), Some(1));
+    assert!(error.render("300").contains("^"));
+
+    let mut de = json::Deserializer::new("1e400".as_bytes()).strict_numbers(true);
+    let error = f64::deserialize(&mut de).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::NumberOutOfRange { ty: "f64", .. }
+    ));
+}
+
+#[test]
+fn string_integers() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Ids {
+        id: u64,
+        parent: i64,
+    }
+
+    let input = r#"{"id":"9007199254740993","parent":-1}"#;
+    assert!(json::from_str::<Ids>(input).unwrap_err().is_data());
+
+    let mut de = json::Deserializer::new(input.as_bytes()).string_integers(true);
+    let json = Ids::deserialize(&mut de).expect("Failed to deserialize");
+    assert_eq!(
+        json,
+        Ids {
+            id: 9007199254740993,
+            parent: -1
+        }
+    );
+
+    let mut de = json::Deserializer::new(r#""1.5""#.as_bytes()).string_integers(true);
+    let error = u64::deserialize(&mut de).unwrap_err();
+    assert!(matches!(error.inner(), json::Error::InvalidValue { .. }));
+
+    let mut de = json::Deserializer::new(r#""300""#.as_bytes()).string_integers(true);
+    let error = u8::deserialize(&mut de).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::NumberOutOfRange { .. }
+    ));
+}
+
+#[test]
+fn lenient() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Order {
+        id: u32,
+        quantity: i64,
+        price: f64,
+        paid: bool,
+        shipped: bool,
+    }
+
+    let input = r#"{"id":"42","quantity":5.0,"price":"9.99","paid":"true","shipped":0}"#;
+    assert!(json::from_str::<Order>(input).unwrap_err().is_data());
+
+    let mut de = json::Deserializer::new(input.as_bytes()).coercion(json::Coercion::Lenient);
+    let json = Order::deserialize(&mut de).expect("Failed to deserialize");
+    assert_eq!(
+        json,
+        Order {
+            id: 42,
+            quantity: 5,
+            price: 9.99,
+            paid: true,
+            shipped: false
+        }
+    );
+
+    fn lenient(input: &str) -> json::Deserializer<&[u8]> {
+        json::Deserializer::new(input.as_bytes()).coercion(json::Coercion::Lenient)
+    }
+    assert_eq!(u32::deserialize(&mut lenient("1.5e3")).ok(), Some(1500));
+    assert_eq!(i8::deserialize(&mut lenient(r#""-2.00""#)).ok(), Some(-2));
+    assert_eq!(f64::deserialize(&mut lenient(r#""0.1""#)).ok(), Some(0.1));
+    assert_eq!(
+        f32::deserialize(&mut lenient(r#""-2.50""#)).ok(),
+        Some(-2.5)
+    );
+    // Numbers that aren't coerced round as usual
+    assert_eq!(
+        f64::deserialize(&mut lenient("9007199254740993")).ok(),
+        Some(9007199254740992.0)
+    );
+
+    // Lossy coercions are still rejected
+    assert!(u32::deserialize(&mut lenient("5.5")).is_err());
+    assert!(u32::deserialize(&mut lenient(r#""forty""#)).is_err());
+    assert!(u8::deserialize(&mut lenient("300.0")).is_err());
+    assert!(bool::deserialize(&mut lenient("2")).is_err());
+    assert!(bool::deserialize(&mut lenient(r#""yes""#)).is_err());
+    assert!(f64::deserialize(&mut lenient(r#""NaN""#)).is_err());
+    assert!(f64::deserialize(&mut lenient(r#""9007199254740993""#)).is_err());
+    assert!(f32::deserialize(&mut lenient(r#""16777217""#)).is_err());
+    assert!(f64::deserialize(&mut lenient(r#""1e400""#)).is_err());
+    assert!(u64::deserialize(&mut lenient("1e9223372036854775807")).is_err());
+    assert!(u64::deserialize(&mut lenient(r#""1e9223372036854775807""#)).is_err());
+    assert!(u64::deserialize(&mut lenient("1e-9223372036854775808")).is_err());
+}
+
+#[test]
+fn name_matching() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct User {
+        user_id: u32,
+        display_name: String,
+        role: Role,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Role {
+        Admin,
+        ReadOnly(bool),
+        Guest { since_date: u32 },
+    }
+
+    fn matching(input: &str, names: json::NameMatching) -> json::Result<User> {
+        let mut de = json::Deserializer::new(input.as_bytes()).name_matching(names);
+        User::deserialize(&mut de)
+    }
+
+    let input = r#"{"UserID":1,"DISPLAYNAME":"droddy","role":"admin"}"#;
+    assert!(matching(input, json::NameMatching::Exact).is_err());
+    assert_eq!(
+        matching(input, json::NameMatching::CaseInsensitive).expect("Failed to deserialize"),
+        User {
+            user_id: 1,
+            display_name: "droddy".to_string(),
+            role: Role::Admin
+        }
+    );
+
+    let input = r#"{"user_id":1,"display-name":"rox","role":{"read_only":true}}"#;
+    assert!(matching(input, json::NameMatching::CaseInsensitive).is_err());
+    assert_eq!(
+        matching(input, json::NameMatching::Normalized).expect("Failed to deserialize"),
+        User {
+            user_id: 1,
+            display_name: "rox".to_string(),
+            role: Role::ReadOnly(true)
+        }
+    );
+
+    let input = r#"{"userId":1,"displayName":"rox","role":{"GUEST":{"SINCE_DATE":3}}}"#;
+    assert!(matching(input, json::NameMatching::Exact).is_err());
+    assert_eq!(
+        matching(input, json::NameMatching::CaseInsensitive).expect("Failed to deserialize"),
+        User {
+            user_id: 1,
+            display_name: "rox".to_string(),
+            role: Role::Guest { since_date: 3 }
+        }
+    );
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Clash {
+        #[serde(rename = "userId")]
+        camel: u32,
+        user_id: u32,
+    }
+
+    let input = r#"{"userId":1,"user_id":2}"#;
+    let mut de =
+        json::Deserializer::new(input.as_bytes()).name_matching(json::NameMatching::Normalized);
+    assert!(Clash::deserialize(&mut de).is_ok());
+
+    let input = r#"{"USER_ID":1}"#;
+    let mut de =
+        json::Deserializer::new(input.as_bytes()).name_matching(json::NameMatching::Normalized);
+    let error = Clash::deserialize(&mut de).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::AmbiguousName { name, candidates }
+            if name == "USER_ID" && *candidates == ["userId", "user_id"]
+    ));
+    assert_eq!(
+        error.path().map(|path| path.to_string()).as_deref(),
+        Some("USER_ID")
+    );
+}
+
 #[test]
 fn float() {
     let input = "[-11.22, 1]";
@@ -87,3 +426,276 @@ fn d_enum() {
     let json = json::from_str::<Test>(input).expect("Failed to deserialize");
     assert_eq!(json, Test::B { a: 1, b: 2 });
 }
+
+#[test]
+fn json5() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Config {
+        name: String,
+        quote: String,
+        mask: u32,
+        ratio: f64,
+        half: f64,
+        limit: f64,
+        ports: Vec<i16>,
+    }
+    let input = r#"
+        // Server configuration
+        {
+            name: 'droddy',
+            "quote": 'He said "hi" \
+and left',
+            mask: 0xFF,
+            ratio: +1.,
+            half: .5,
+            /* no upper bound */
+            limit: Infinity,
+            ports: [80, -0x1BB,],
+        }
+    "#;
+    let json = json::from_str_json5::<Config>(input).expect("Failed to deserialize");
+    assert_eq!(
+        json,
+        Config {
+            name: "droddy".to_string(),
+            quote: "He said \"hi\" and left".to_string(),
+            mask: 255,
+            ratio: 1.0,
+            half: 0.5,
+            limit: f64::INFINITY,
+            ports: vec![80, -443],
+        }
+    );
+}
+
+#[test]
+fn json5_only_when_enabled() {
+    assert!(json::from_str::<Vec<u8>>("[1,2,]").is_err());
+    assert!(json::from_str::<Vec<u8>>("[0x1]").is_err());
+    assert!(json::from_str::<String>("'droddyrox'").is_err());
+}
+
+#[test]
+fn escaped_backslash() {
+    let input = r#"["droddy\\", "rox"]"#;
+    let json = json::from_str::<Vec<String>>(input).expect("Failed to deserialize");
+    assert_eq!(json, ["droddy\\", "rox"]);
+}
+
+#[test]
+fn jsonc() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Settings {
+        url: String,
+        channels: Vec<String>,
+    }
+    let input = r#"
+        {
+            // Where to fetch updates from
+            "url": "https://example.com//updates", /* the // in the string literal is kept */
+            "channels": ["stable", "beta",],
+        }
+    "#;
+    let json = json::from_str_jsonc::<Settings>(input).expect("Failed to deserialize");
+    assert_eq!(
+        json,
+        Settings {
+            url: "https://example.com//updates".to_string(),
+            channels: vec!["stable".to_string(), "beta".to_string()],
+        }
+    );
+
+    // Only comments and trailing commas are relaxed
+    assert!(json::from_str_jsonc::<Vec<u8>>("[1,,]").is_err());
+    assert!(json::from_str_jsonc::<Vec<String>>("['droddyrox']").is_err());
+}
+
+#[test]
+fn error_category() {
+    let truncated = json::from_str::<Vec<u8>>("[1, 2").unwrap_err();
+    assert!(truncated.is_eof());
+    let unclosed = json::from_str::<String>(r#""droddy"#).unwrap_err();
+    assert!(unclosed.is_eof());
+    let malformed = json::from_str::<Vec<u8>>("[1 2]").unwrap_err();
+    assert!(malformed.is_syntax());
+    let bad_number = json::from_str::<f64>("1e").unwrap_err();
+    assert!(bad_number.is_syntax());
+    let wrong_type = json::from_str::<u8>(r#""droddyrox""#).unwrap_err();
+    assert!(wrong_type.is_data());
+    let overflow = json::from_str::<u8>("300").unwrap_err();
+    assert_eq!(overflow.classify(), json::Category::Data);
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    enum Test {
+        A(u8),
+        B(u8),
+    }
+    let truncated = json::from_str::<Test>(r#"{"A":1"#).unwrap_err();
+    assert!(truncated.is_eof());
+    let two_variants = json::from_str::<Test>(r#"{"A":1,"B":2}"#).unwrap_err();
+    assert!(two_variants.is_syntax());
+}
+
+#[test]
+fn error_path() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Config {
+        servers: Vec<Server>,
+    }
+    let input = r#"{"servers": [
+        {"host": "a", "port": 1},
+        {"host": "b", "port": 2},
+        {"host": "c", "port": 3},
+        {"host": "d", "port": "droddyrox"}
+    ]}"#;
+    let error = json::from_str::<Config>(input).unwrap_err();
+    assert_eq!(error.path().unwrap().to_string(), "servers[3].port");
+    assert!(error.is_data());
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    enum Shape {
+        Circle { radius: f64 },
+    }
+    let error = json::from_str::<Vec<Shape>>(r#"[{"Circle": {"radius": null}}]"#).unwrap_err();
+    assert_eq!(error.path().unwrap().to_string(), "[0].Circle.radius");
+
+    let error = json::from_str::<u8>("null").unwrap_err();
+    assert!(error.path().is_none());
+}
+
+#[test]
+fn data_errors() {
+    use json::error::Unexpected;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let error = json::from_str::<Server>(r#"{"host": 1, "port": 1}"#).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::InvalidType {
+            unexpected: Unexpected::Unsigned(1),
+            expected,
+        } if expected == "a string"
+    ));
+
+    let error = json::from_str::<Server>(r#"{"host": "a", "prot": 1}"#).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::UnknownField { field, expected, .. } if field == "prot" && *expected == ["host", "port"]
+    ));
+
+    let error = json::from_str::<Server>(r#"{"host": "a"}"#).unwrap_err();
+    assert!(matches!(error.inner(), json::Error::MissingField("port")));
+
+    let error = json::from_str::<(u8, u8)>("[1]").unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::InvalidLength { len: 1, .. }
+    ));
+
+    let error = json::from_str::<(u8, u8)>("[1, 2, [3], 4]").unwrap_err();
+    assert!(error.is_data());
+    assert!(matches!(
+        error.inner(),
+        json::Error::InvalidLength { len: 4, expected } if expected == "2 elements"
+    ));
+    assert_eq!(error.position().map(|p| p.column), Some(8));
+}
+
+#[test]
+fn render() {
+    let input = "{\n  \"name\": \"droddy\",\n  \"age\": 'rox'\n}";
+    let error = json::from_str::<std::collections::HashMap<String, String>>(input).unwrap_err();
+    let expected = r#"error: Unexpected character '
+ --> line 3, column 10
+  |
+3 |   "age": 'rox'
+  |          ^
+  = path: age
+  = hint: strings and keys must use double quotes
+"#;
+    assert_eq!(error.render(input), expected);
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Person {
+        age: u8,
+    }
+    let input = "[{\"age\": 300}]";
+    let error = json::from_str::<Vec<Person>>(input).unwrap_err();
+    let rendered = error.render(input);
+    assert!(rendered.contains("--> line 1, column 10"));
+    assert!(rendered.contains("  = path: [0].age"));
+
+    let input = "[1, 2,]";
+    let error = json::from_str::<Vec<u8>>(input).unwrap_err();
+    assert!(matches!(error.inner(), json::Error::TrailingComma(']')));
+    assert!(
+        error
+            .render(input)
+            .contains("  = hint: remove the trailing comma")
+    );
+    for input in [r#"{"a":}"#, "[}"] {
+        let error = json::from_str::<serde::de::IgnoredAny>(input).unwrap_err();
+        assert!(!error.render(input).contains("trailing comma"));
+    }
+}
+
+#[test]
+fn suggestions() {
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+    let error = json::from_str::<Server>(r#"{"host": "a", "prot": 1}"#).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::UnknownField {
+            suggestion: Some("port"),
+            ..
+        }
+    ));
+    assert!(error.to_string().contains("did you mean `port`?"));
+    let input = r#"{"host": "a", "prot": 1}"#;
+    assert_eq!(error.render(input).matches("did you mean").count(), 1);
+
+    #[derive(Debug, Deserialize)]
+    enum Shape {
+        Circle,
+        Square,
+    }
+    let error = json::from_str::<Shape>(r#""Circel""#).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::UnknownVariant {
+            suggestion: Some("Circle"),
+            ..
+        }
+    ));
+    let error = json::from_str::<Shape>(r#""Triangle""#).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        json::Error::UnknownVariant {
+            suggestion: None,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json_exercise as json;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+/// Serialize `value`, check the output, and deserialize it back
+fn round_trip<T>(value: &T, expected: &str)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let string = json::to_string(value).expect("Failed to serialize");
+    assert_eq!(string, expected);
+    let back = json::from_str::<T>(&string).expect("Failed to deserialize");
+    assert_eq!(&back, value);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn externally_tagged() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Message {
+        Ping,
+        Move(Point),
+        Pair(u8, String),
+        Resize { width: u32, height: u32 },
+    }
+
+    round_trip(&Message::Ping, r#""Ping""#);
+    round_trip(
+        &Message::Move(Point { x: 1, y: -2 }),
+        r#"{"Move":{"x":1,"y":-2}}"#,
+    );
+    round_trip(
+        &Message::Pair(1, "droddy".to_string()),
+        r#"{"Pair":[1,"droddy"]}"#,
+    );
+    round_trip(
+        &Message::Resize {
+            width: 3,
+            height: 4,
+        },
+        r#"{"Resize":{"width":3,"height":4}}"#,
+    );
+
+    let json = json::from_str::<Message>(r#"{"Ping":null}"#).expect("Failed to deserialize");
+    assert_eq!(json, Message::Ping);
+}
+
+#[test]
+fn internally_tagged() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    enum Message {
+        Ping,
+        Move(Point),
+        Resize { width: u32, height: u32 },
+        Rename { name: String, tags: Vec<String> },
+    }
+
+    round_trip(&Message::Ping, r#"{"type":"Ping"}"#);
+    round_trip(
+        &Message::Move(Point { x: 1, y: -2 }),
+        r#"{"type":"Move","x":1,"y":-2}"#,
+    );
+    round_trip(
+        &Message::Resize {
+            width: 3,
+            height: 4,
+        },
+        r#"{"type":"Resize","width":3,"height":4}"#,
+    );
+    round_trip(
+        &Message::Rename {
+            name: "rox".to_string(),
+            tags: vec!["a".to_string()],
+        },
+        r#"{"type":"Rename","name":"rox","tags":["a"]}"#,
+    );
+
+    let input = r#"{"width":3,"height":4,"type":"Resize"}"#;
+    let json = json::from_str::<Message>(input).expect("Failed to deserialize");
+    assert_eq!(
+        json,
+        Message::Resize {
+            width: 3,
+            height: 4
+        }
+    );
+
+    let error = json::from_str::<Message>(r#"{"type":"Jump"}"#).unwrap_err();
+    assert!(error.is_data());
+}
+
+#[test]
+fn adjacently_tagged() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "t", content = "c")]
+    enum Message {
+        Ping,
+        Move(Point),
+        Pair(u8, String),
+        Resize { width: u32, height: u32 },
+    }
+
+    round_trip(&Message::Ping, r#"{"t":"Ping"}"#);
+    round_trip(
+        &Message::Move(Point { x: 1, y: -2 }),
+        r#"{"t":"Move","c":{"x":1,"y":-2}}"#,
+    );
+    round_trip(
+        &Message::Pair(1, "droddy".to_string()),
+        r#"{"t":"Pair","c":[1,"droddy"]}"#,
+    );
+    round_trip(
+        &Message::Resize {
+            width: 3,
+            height: 4,
+        },
+        r#"{"t":"Resize","c":{"width":3,"height":4}}"#,
+    );
+
+    let input = r#"{"c":[1,"droddy"],"t":"Pair"}"#;
+    let json = json::from_str::<Message>(input).expect("Failed to deserialize");
+    assert_eq!(json, Message::Pair(1, "droddy".to_string()));
+}
+
+#[test]
+fn untagged() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Null,
+        Bool(bool),
+        Unsigned(u64),
+        Signed(i64),
+        Float(f64),
+        String(String),
+        Point(Point),
+        List(Vec<Value>),
+    }
+
+    round_trip(&Value::Null, "null");
+    round_trip(&Value::Bool(true), "true");
+    round_trip(&Value::Unsigned(u64::MAX), "18446744073709551615");
+    round_trip(&Value::Signed(i64::MIN), "-9223372036854775808");
+    round_trip(&Value::Float(1.5), "1.5");
+    round_trip(&Value::String("droddy".to_string()), r#""droddy""#);
+    round_trip(&Value::Point(Point { x: 1, y: -2 }), r#"{"x":1,"y":-2}"#);
+    round_trip(
+        &Value::List(vec![Value::Unsigned(1), Value::Null]),
+        "[1,null]",
+    );
+
+    let input = "1000000000000000000000000000000000000000";
+    let json = json::from_str::<Value>(input).expect("Failed to deserialize");
+    assert_eq!(json, Value::Float(1e39));
+
+    let error = json::from_str::<Value>(r#"{"x":1}"#).unwrap_err();
+    assert!(error.is_data());
+}
+
+#[test]
+fn flatten() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Request {
+        id: u32,
+        #[serde(flatten)]
+        point: Point,
+        #[serde(flatten)]
+        extra: BTreeMap<String, String>,
+    }
+
+    round_trip(
+        &Request {
+            id: 7,
+            point: Point { x: 1, y: -2 },
+            extra: BTreeMap::from([("name".to_string(), "rox".to_string())]),
+        },
+        r#"{"id":7,"x":1,"y":-2,"name":"rox"}"#,
+    );
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    enum Shape {
+        Circle { radius: u32 },
+        Square { side: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Tagged {
+        id: u32,
+        #[serde(flatten)]
+        shape: Shape,
+    }
+
+    round_trip(
+        &Tagged {
+            id: 1,
+            shape: Shape::Circle { radius: 2 },
+        },
+        r#"{"id":1,"type":"Circle","radius":2}"#,
+    );
+}
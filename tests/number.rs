@@ -0,0 +1,133 @@
+#![cfg(feature = "arbitrary_precision")]
+
+use serde::{Deserialize, Serialize};
+use serde_json_exercise as json;
+
+#[test]
+fn round_trip() {
+    let input = r#"[3.141592653589793238462643,1234567890123456789012345678901234567890,-1e-400]"#;
+    let json = json::from_str::<Vec<json::Number>>(input).expect("Failed to deserialize");
+    assert_eq!(json[0].as_str(), "3.141592653589793238462643");
+    assert_eq!(json[1].as_str(), "1234567890123456789012345678901234567890");
+    assert_eq!(json::to_string(&json).expect("Failed to serialize"), input);
+}
+
+#[test]
+fn in_struct() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Reading {
+        sensor: String,
+        value: json::Number,
+    }
+
+    let input = r#"{"sensor":"droddy","value":0.10000000000000000000001}"#;
+    let json = json::from_str::<Reading>(input).expect("Failed to deserialize");
+    assert_eq!(json.value.to_string(), "0.10000000000000000000001");
+    assert_eq!(json::to_string(&json).expect("Failed to serialize"), input);
+
+    let error = json::from_str::<Reading>(r#"{"sensor":"droddy","value":"1"}"#).unwrap_err();
+    assert!(error.is_data());
+}
+
+#[test]
+fn conversions() {
+    let number = json::from_str::<json::Number>("300").expect("Failed to deserialize");
+    assert!(number.is_integer());
+    assert_eq!(number.as_u8(), None);
+    assert_eq!(number.as_u16(), Some(300));
+    assert_eq!(number.as_i64(), Some(300));
+    assert_eq!(number.as_f64(), Some(300.0));
+
+    let number = json::from_str::<json::Number>("-2.5e3").expect("Failed to deserialize");
+    assert!(!number.is_integer());
+    assert_eq!(number.as_i64(), None);
+    assert_eq!(number.as_f64(), Some(-2500.0));
+
+    let number = json::from_str::<json::Number>("1e400").expect("Failed to deserialize");
+    assert_eq!(number.as_f64(), None);
+
+    assert_eq!(json::Number::from(u128::MAX).as_u128(), Some(u128::MAX));
+    assert_eq!(
+        "12".parse::<json::Number>()
+            .expect("Failed to parse")
+            .as_u8(),
+        Some(12)
+    );
+    assert!("012".parse::<json::Number>().is_err());
+    assert!("1+2".parse::<json::Number>().is_err());
+    assert_eq!(
+        "1e+2"
+            .parse::<json::Number>()
+            .expect("Failed to parse")
+            .as_f64(),
+        Some(100.0)
+    );
+}
+
+#[test]
+fn json5() {
+    let input = "[+1, 0x1F]";
+    let json = json::from_str_json5::<Vec<json::Number>>(input).expect("Failed to deserialize");
+    assert_eq!(json[0].as_str(), "1");
+    assert_eq!(json[1].as_str(), "31");
+    assert!(json::from_str_json5::<json::Number>("Infinity").is_err());
+
+    let json = json::from_str_json5::<Vec<json::Number>>("[.5, 5., -.5e1]")
+        .expect("Failed to deserialize");
+    assert_eq!(json[0].as_str(), "0.5");
+    assert_eq!(json[1].as_str(), "5.0");
+    assert_eq!(json[2].as_str(), "-0.5e1");
+    assert!(".".parse::<json::Number>().is_err());
+}
+
+#[test]
+fn buffered() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Number(json::Number),
+        String(String),
+    }
+
+    let json = json::from_str::<Value>("42").expect("Failed to deserialize");
+    assert_eq!(json, Value::Number(42u8.into()));
+
+    // Serde buffers the number as an f64, so the extra digits are lost
+    let json =
+        json::from_str::<Value>("3.141592653589793238462643").expect("Failed to deserialize");
+    let Value::Number(number) = json else {
+        panic!("Expected a number, got {json:?}");
+    };
+    assert_eq!(number.as_f64(), Some(std::f64::consts::PI));
+
+    // Other types in buffered content are unaffected by the feature
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(untagged)]
+    enum Primitive {
+        Integer(u64),
+        Float(f64),
+    }
+
+    let json = json::from_str::<Vec<Primitive>>("[7,0.5,3.141592653589793238462643]")
+        .expect("Failed to deserialize");
+    assert_eq!(
+        json,
+        [
+            Primitive::Integer(7),
+            Primitive::Float(0.5),
+            Primitive::Float(std::f64::consts::PI)
+        ]
+    );
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Reading {
+        sensor: String,
+        #[serde(flatten)]
+        values: std::collections::BTreeMap<String, f64>,
+    }
+
+    let input = r#"{"sensor":"droddy","low":1e-400,"high":3.141592653589793238462643}"#;
+    let json = json::from_str::<Reading>(input).expect("Failed to deserialize");
+    assert_eq!(json.values["low"], 0.0);
+    assert_eq!(json.values["high"], std::f64::consts::PI);
+}
@@ -41,10 +41,362 @@ fn map() {
 
 #[test]
 fn bad_key() {
-    let map = std::collections::HashMap::from([(1, 1), (2, 2)]);
+    let map = std::collections::HashMap::from([((1, 2), 1)]);
     assert!(json::to_string(&map).is_err());
 }
 
+#[test]
+fn non_string_keys() {
+    let map = std::collections::BTreeMap::from([(-1, 1), (2, 2)]);
+    assert_eq!(
+        json::to_string(&map).expect("Failed to serialize"),
+        r#"{"-1":1,"2":2}"#
+    );
+
+    let map = std::collections::BTreeMap::from([(false, 'a'), (true, 'b')]);
+    assert_eq!(
+        json::to_string(&map).expect("Failed to serialize"),
+        r#"{"false":"a","true":"b"}"#
+    );
+
+    #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+    enum Color {
+        Red,
+    }
+    let map = std::collections::BTreeMap::from([(Color::Red, 1)]);
+    assert_eq!(
+        json::to_string(&map).expect("Failed to serialize"),
+        r#"{"Red":1}"#
+    );
+}
+
+#[test]
+fn int128() {
+    assert_eq!(
+        json::to_string(&(i128::MIN, u128::MAX)).expect("Failed to serialize"),
+        "[-170141183460469231731687303715884105728,340282366920938463463374607431768211455]"
+    );
+
+    let map = std::collections::BTreeMap::from([(u128::MAX, 1)]);
+    assert_eq!(
+        json::to_string(&map).expect("Failed to serialize"),
+        r#"{"340282366920938463463374607431768211455":1}"#
+    );
+}
+
+#[test]
+fn integer_format() {
+    #[derive(Serialize)]
+    struct Ids {
+        small: u8,
+        safe: u64,
+        large: u64,
+        negative: i64,
+        wide: i128,
+    }
+    let ids = Ids {
+        small: 1,
+        safe: 9007199254740991,
+        large: 9007199254740993,
+        negative: -9007199254740992,
+        wide: 2,
+    };
+    let write = |format| {
+        let mut out = Vec::new();
+        ids.serialize(&mut json::Serializer::new(&mut out).integer_format(format))
+            .expect("Failed to serialize");
+        String::from_utf8(out).expect("Failed to read UTF-8")
+    };
+
+    assert_eq!(
+        write(json::IntegerFormat::Number),
+        r#"{"small":1,"safe":9007199254740991,"large":9007199254740993,"negative":-9007199254740992,"wide":2}"#
+    );
+    assert_eq!(
+        write(json::IntegerFormat::LargeAsString),
+        r#"{"small":1,"safe":9007199254740991,"large":"9007199254740993","negative":"-9007199254740992","wide":2}"#
+    );
+    assert_eq!(
+        write(json::IntegerFormat::WideAsString),
+        r#"{"small":1,"safe":"9007199254740991","large":"9007199254740993","negative":"-9007199254740992","wide":"2"}"#
+    );
+}
+
+#[test]
+fn key_case() {
+    assert_eq!(json::KeyCase::SnakeCase.apply("userID"), "user_id");
+    assert_eq!(json::KeyCase::SnakeCase.apply("HTTPServer"), "http_server");
+    assert_eq!(json::KeyCase::CamelCase.apply("user_id"), "userId");
+    assert_eq!(json::KeyCase::PascalCase.apply("user-id"), "UserId");
+    assert_eq!(json::KeyCase::KebabCase.apply("ReadOnly"), "read-only");
+    assert_eq!(
+        json::KeyCase::ScreamingSnakeCase.apply("maxRetries2"),
+        "MAX_RETRIES2"
+    );
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    enum Role {
+        Admin,
+        ReadOnly { since_date: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct User {
+        user_id: u32,
+        roles: Vec<Role>,
+        extra: std::collections::BTreeMap<String, u8>,
+    }
+
+    let user = User {
+        user_id: 1,
+        roles: vec![Role::Admin, Role::ReadOnly { since_date: 2 }],
+        extra: std::collections::BTreeMap::from([("login_count".to_string(), 3)]),
+    };
+    let write = |rename_map_keys| {
+        let mut out = Vec::new();
+        let mut serializer = json::Serializer::new(&mut out)
+            .key_case(json::KeyCase::CamelCase)
+            .rename_map_keys(rename_map_keys);
+        user.serialize(&mut serializer)
+            .expect("Failed to serialize");
+        String::from_utf8(out).expect("Failed to read UTF-8")
+    };
+
+    let output = write(false);
+    assert_eq!(
+        output,
+        r#"{"userId":1,"roles":["admin",{"readOnly":{"sinceDate":2}}],"extra":{"login_count":3}}"#
+    );
+    assert_eq!(
+        write(true),
+        r#"{"userId":1,"roles":["admin",{"readOnly":{"sinceDate":2}}],"extra":{"loginCount":3}}"#
+    );
+
+    let mut de = json::Deserializer::new(output.as_bytes()).key_case(json::KeyCase::CamelCase);
+    let json = <User as serde::Deserialize>::deserialize(&mut de).expect("Failed to deserialize");
+    assert_eq!(json, user);
+
+    // Serde buffers internally tagged enums, so they're renamed with its own attributes
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    #[serde(tag = "type", rename_all_fields = "camelCase")]
+    enum Command {
+        Resize { new_width: u32 },
+    }
+
+    let command = Command::Resize { new_width: 3 };
+    let mut out = Vec::new();
+    let mut serializer = json::Serializer::new(&mut out).key_case(json::KeyCase::CamelCase);
+    command
+        .serialize(&mut serializer)
+        .expect("Failed to serialize");
+    assert_eq!(out, br#"{"type":"Resize","newWidth":3}"#);
+
+    let mut de = json::Deserializer::new(&out[..]).key_case(json::KeyCase::CamelCase);
+    let json =
+        <Command as serde::Deserialize>::deserialize(&mut de).expect("Failed to deserialize");
+    assert_eq!(json, command);
+}
+
+#[test]
+fn redact() {
+    #[derive(Serialize)]
+    struct Card {
+        number: String,
+        expiry: (u8, u16),
+    }
+
+    #[derive(Serialize)]
+    struct Payment {
+        amount: u32,
+        card: Card,
+    }
+
+    #[derive(Serialize)]
+    struct Request {
+        user: String,
+        password: String,
+        headers: std::collections::BTreeMap<String, String>,
+        payment: Payment,
+        items: Vec<u32>,
+    }
+
+    let request = Request {
+        user: "droddy".to_string(),
+        password: "hunter2".to_string(),
+        headers: std::collections::BTreeMap::from([
+            ("api_secret".to_string(), "abc".to_string()),
+            ("token".to_string(), "xyz".to_string()),
+            ("accept".to_string(), "json".to_string()),
+        ]),
+        payment: Payment {
+            amount: 5,
+            card: Card {
+                number: "4111".to_string(),
+                expiry: (1, 2030),
+            },
+        },
+        items: vec![1, 2, 3],
+    };
+    let write = |redactor: &json::Redactor| {
+        let mut out = Vec::new();
+        request
+            .serialize(&mut json::Serializer::new(&mut out).redact(redactor))
+            .expect("Failed to serialize");
+        String::from_utf8(out).expect("Failed to read UTF-8")
+    };
+
+    let redactor = json::Redactor::new()
+        .field("password")
+        .field("token")
+        .glob("*_secret")
+        .pointer("/payment/card")
+        .pointer("/items/1");
+    assert_eq!(
+        write(&redactor),
+        r#"{"user":"droddy","password":"[REDACTED]","headers":{"accept":"json","api_secret":"[REDACTED]","token":"[REDACTED]"},"payment":{"amount":5,"card":"[REDACTED]"},"items":[1,"[REDACTED]",3]}"#
+    );
+
+    const KEY: [u8; 16] = *b"droddy-rox-01234";
+    let hashed = write(&json::Redactor::new().field("password").hash(KEY));
+    assert!(!hashed.contains("hunter2"));
+    assert_eq!(
+        hashed,
+        write(&json::Redactor::new().field("password").hash(KEY))
+    );
+    assert_ne!(
+        hashed,
+        write(&json::Redactor::new().field("user").hash(KEY))
+    );
+    assert_ne!(
+        hashed,
+        write(&json::Redactor::new().field("password").hash([0; 16]))
+    );
+
+    assert_eq!(
+        write(&json::Redactor::new()),
+        json::to_string(&request).expect("Failed to serialize")
+    );
+    #[derive(Serialize)]
+    enum Login {
+        Password { user: String, password: String },
+        Token(String, String),
+    }
+
+    let logins = vec![
+        Login::Password {
+            user: "rox".to_string(),
+            password: "hunter2".to_string(),
+        },
+        Login::Token("rox".to_string(), "xyz".to_string()),
+    ];
+    let redactor = json::Redactor::new()
+        .field("password")
+        .pointer("/1/Token/1");
+    let mut out = Vec::new();
+    logins
+        .serialize(&mut json::Serializer::new(&mut out).redact(&redactor))
+        .expect("Failed to serialize");
+    assert_eq!(
+        String::from_utf8(out).expect("Failed to read UTF-8"),
+        r#"[{"Password":{"user":"rox","password":"[REDACTED]"}},{"Token":["rox","[REDACTED]"]}]"#
+    );
+}
+
+#[test]
+fn projected() {
+    #[derive(Serialize)]
+    struct Owner {
+        name: String,
+        email: String,
+    }
+
+    #[derive(Serialize)]
+    struct Tag {
+        id: u32,
+        label: String,
+    }
+
+    #[derive(Serialize)]
+    struct Repo {
+        id: u32,
+        name: String,
+        owner: Owner,
+        tags: Vec<Tag>,
+        meta: std::collections::BTreeMap<String, u32>,
+    }
+
+    let repo = Repo {
+        id: 1,
+        name: "droddy".to_string(),
+        owner: Owner {
+            name: "rox".to_string(),
+            email: "rox@example.com".to_string(),
+        },
+        tags: vec![
+            Tag {
+                id: 2,
+                label: "a".to_string(),
+            },
+            Tag {
+                id: 3,
+                label: "b".to_string(),
+            },
+        ],
+        meta: std::collections::BTreeMap::from([
+            ("forks".to_string(), 4),
+            ("stars".to_string(), 5),
+        ]),
+    };
+
+    assert_eq!(
+        json::to_string_projected(&repo, &["id", "name", "owner/email"])
+            .expect("Failed to serialize"),
+        r#"{"id":1,"name":"droddy","owner":{"email":"rox@example.com"}}"#
+    );
+    assert_eq!(
+        json::to_string_projected(&repo, &["owner/email", "tags/label", "meta/stars"])
+            .expect("Failed to serialize"),
+        r#"{"owner":{"email":"rox@example.com"},"tags":[{"label":"a"},{"label":"b"}],"meta":{"stars":5}}"#
+    );
+    assert_eq!(
+        json::to_string_projected(&repo, &["owner"]).expect("Failed to serialize"),
+        r#"{"owner":{"name":"rox","email":"rox@example.com"}}"#
+    );
+    assert_eq!(
+        json::to_string_projected(&repo, &["missing"]).expect("Failed to serialize"),
+        "{}"
+    );
+    assert_eq!(
+        json::to_string_projected(&vec![1, 2], &["id"]).expect("Failed to serialize"),
+        "[1,2]"
+    );
+    assert_eq!(
+        json::to_string_projected(&repo, &["owner/missing", "id"]).expect("Failed to serialize"),
+        r#"{"id":1,"owner":{}}"#
+    );
+
+    #[derive(Serialize)]
+    struct Nested {
+        a: Vec<u32>,
+        b: std::collections::BTreeMap<String, u32>,
+        c: u32,
+    }
+
+    let nested = Nested {
+        a: vec![],
+        b: std::collections::BTreeMap::new(),
+        c: 1,
+    };
+    assert_eq!(
+        json::to_string(&nested).expect("Failed to serialize"),
+        r#"{"a":[],"b":{},"c":1}"#
+    );
+    assert_eq!(
+        json::to_string_projected(&nested, &["b", "c"]).expect("Failed to serialize"),
+        r#"{"b":{},"c":1}"#
+    );
+}
+
 #[test]
 fn tuple() {
     assert_eq!(
@@ -0,0 +1,72 @@
+use serde_json_exercise::{
+    self as json, Token, Tokenizer,
+    tokenizer::{Event, Position},
+};
+
+#[test]
+fn tokens() {
+    let input = r#"{"a": [1e3, "droddy", true, null], "b": {}}"#;
+    let tokens = Tokenizer::new(input.as_bytes())
+        .map(|event| event.map(|event| event.token))
+        .collect::<json::Result<Vec<_>>>()
+        .expect("Failed to tokenize");
+    assert_eq!(
+        tokens,
+        [
+            Token::StartObject,
+            Token::Key("a".to_string()),
+            Token::StartArray,
+            Token::Number("1e3".to_string()),
+            Token::String("droddy".to_string()),
+            Token::Bool(true),
+            Token::Null,
+            Token::EndArray,
+            Token::Key("b".to_string()),
+            Token::StartObject,
+            Token::EndObject,
+            Token::EndObject,
+        ]
+    );
+}
+
+#[test]
+fn positions() {
+    let input = "[\n  \"é\", 12\n]";
+    let mut tokens = Tokenizer::new(input.as_bytes());
+    let mut next = || tokens.next_event().expect("Failed to tokenize");
+    next();
+    next();
+    assert_eq!(
+        next(),
+        Some(Event {
+            token: Token::Number("12".to_string()),
+            position: Position {
+                line: 2,
+                column: 8,
+                offset: 10,
+            },
+        })
+    );
+    assert_eq!(next().map(|event| event.position.line), Some(3));
+    assert_eq!(next(), None);
+}
+
+#[test]
+fn malformed() {
+    for input in ["[1 2]", r#"{"a" 1}"#, r#"{"a":1,}"#, "[1,]", "{1:2}"] {
+        let result = Tokenizer::new(input.as_bytes()).collect::<json::Result<Vec<_>>>();
+        assert!(result.is_err(), "{input} should not tokenize");
+    }
+
+    let mut tokens = Tokenizer::new("[1 2]".as_bytes());
+    assert!(matches!(tokens.next(), Some(Ok(_))));
+    assert!(matches!(tokens.next(), Some(Ok(_))));
+    assert!(matches!(tokens.next(), Some(Err(_))));
+    assert!(tokens.next().is_none());
+    assert_eq!(
+        Tokenizer::new("[1 2]".as_bytes())
+            .filter_map(Result::ok)
+            .count(),
+        2
+    );
+}
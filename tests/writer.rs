@@ -0,0 +1,64 @@
+use serde::Serialize;
+use serde_json_exercise::{self as json, JsonWriter};
+
+#[test]
+fn write() {
+    #[derive(Serialize)]
+    struct Row {
+        id: u32,
+    }
+
+    let mut writer = JsonWriter::new(Vec::new());
+    writer.begin_object().unwrap();
+    writer.key("name").unwrap();
+    writer.value(&"droddy\"rox").unwrap();
+    writer.key("rows").unwrap();
+    writer.begin_array().unwrap();
+    for id in 0..3 {
+        writer.value(&Row { id }).unwrap();
+    }
+    writer.end().unwrap();
+    writer.key("empty").unwrap();
+    writer.begin_object().unwrap();
+    writer.end().unwrap();
+    writer.end().unwrap();
+    let out = writer.finish().expect("Failed to write");
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"{"name":"droddy\"rox","rows":[{"id":0},{"id":1},{"id":2}],"empty":{}}"#
+    );
+}
+
+#[test]
+fn malformed() {
+    let mut writer = JsonWriter::new(Vec::new());
+    writer.begin_object().unwrap();
+    assert!(matches!(
+        writer.value(&1),
+        Err(json::Error::Misplaced { found: "value", .. })
+    ));
+    writer.key("a").unwrap();
+    assert!(writer.key("b").is_err());
+    assert!(writer.end().is_err());
+    writer.value(&1).unwrap();
+    assert!(matches!(writer.finish(), Err(json::Error::Unclosed('{'))));
+
+    let mut writer = JsonWriter::new(Vec::new());
+    writer.value(&1).unwrap();
+    assert!(writer.value(&2).is_err());
+    assert!(writer.end().is_err());
+
+    assert!(JsonWriter::new(Vec::new()).finish().is_err());
+}
+
+#[test]
+fn failed_value() {
+    let mut writer = JsonWriter::new(Vec::new());
+    writer.begin_array().unwrap();
+    let bad = std::collections::BTreeMap::from([(vec![1u8], 1)]);
+    assert!(writer.value(&bad).is_err());
+    writer.value(&1).unwrap();
+    writer.end().unwrap();
+    let out = writer.finish().expect("Failed to write");
+    assert_eq!(String::from_utf8(out).unwrap(), "[1]");
+}